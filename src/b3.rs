@@ -0,0 +1,129 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use log::warn;
+use uuid::Uuid;
+
+use crate::{ExtractRequestId, RequestIdRejection, config::with_default_config};
+
+const HEADER_X_B3_TRACE_ID: &str = "X-B3-TraceId";
+
+/// This is a custom extractor for Axum that falls back to the Zipkin `X-B3-TraceId` header when `X-Request-Id` is missing.
+///
+/// The precedence is :
+///
+/// 1. the `X-Request-Id` header, validated as an UUID v7, exactly like `ExtractRequestId` ;
+/// 2. the `X-B3-TraceId` header, without the UUID v7 validation, formatted as an hyphenated lowercase UUID
+///    (a 64-bit trace id is left-padded with zeros to 128 bits) ;
+/// 3. a newly generated UUID v7, or the value resolved by a `RequestIdLayer`.
+///
+/// A malformed `X-B3-TraceId` (neither 16 nor 32 hexadecimal characters) is ignored.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::ExtractB3RequestId;
+///
+/// async fn handler(ExtractB3RequestId(request_id): ExtractB3RequestId) {
+///     println!("Request Id: {:?}", request_id);
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractB3RequestId(pub String);
+
+impl<S> FromRequestParts<S> for ExtractB3RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let has_request_id =
+            with_default_config(|config| parts.headers.contains_key(config.header_name()));
+
+        if !has_request_id && let Some(trace_id) = parts.headers.get(HEADER_X_B3_TRACE_ID) {
+            match trace_id.to_str().ok().and_then(parse_trace_id) {
+                Some(trace_id) => return Ok(ExtractB3RequestId(trace_id)),
+                None => warn!(
+                    "[{}] Ignored malformed trace id : {:?}",
+                    HEADER_X_B3_TRACE_ID, trace_id
+                ),
+            }
+        }
+
+        let ExtractRequestId(request_id) =
+            ExtractRequestId::from_request_parts(parts, state).await?;
+
+        Ok(ExtractB3RequestId(request_id))
+    }
+}
+
+/// Parses a 64-bit or 128-bit hexadecimal B3 trace id into an hyphenated lowercase UUID.
+fn parse_trace_id(trace_id: &str) -> Option<String> {
+    let trace_id = trace_id.trim();
+
+    if !(trace_id.len() == 16 || trace_id.len() == 32)
+        || !trace_id.bytes().all(|byte| byte.is_ascii_hexdigit())
+    {
+        return None;
+    }
+
+    let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+
+    Some(Uuid::from_u128(trace_id).hyphenated().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ExtractB3RequestId;
+    use axum::{body::Body, extract::FromRequestParts, http::Request};
+
+    async fn extract(headers: &[(&str, &str)]) -> ExtractB3RequestId {
+        let mut request = Request::builder();
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let mut parts = request.body(Body::empty()).unwrap().into_parts();
+
+        ExtractB3RequestId::from_request_parts(&mut parts.0, &())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_b3_only_128_bits() {
+        let ExtractB3RequestId(request_id) =
+            extract(&[("X-B3-TraceId", "463AC35C9F6413AD48485A3953BB6124")]).await;
+
+        assert_eq!(request_id, "463ac35c-9f64-13ad-4848-5a3953bb6124");
+    }
+
+    #[tokio::test]
+    async fn test_b3_only_64_bits() {
+        let ExtractB3RequestId(request_id) = extract(&[("X-B3-TraceId", "463ac35c9f6413ad")]).await;
+
+        assert_eq!(request_id, "00000000-0000-0000-463a-c35c9f6413ad");
+    }
+
+    #[tokio::test]
+    async fn test_b3_header_wins_over_b3() {
+        let ExtractB3RequestId(request_id) = extract(&[
+            ("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e"),
+            ("X-B3-TraceId", "463ac35c9f6413ad48485a3953bb6124"),
+        ])
+        .await;
+
+        assert_eq!(request_id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+
+    #[tokio::test]
+    async fn test_b3_malformed_is_ignored() {
+        let ExtractB3RequestId(request_id) = extract(&[("X-B3-TraceId", "not-a-trace-id")]).await;
+
+        assert_eq!(request_id.len(), 36);
+        assert_ne!(request_id, "not-a-trace-id");
+    }
+}
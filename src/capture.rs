@@ -0,0 +1,141 @@
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use axum::extract::Request;
+use tower::{Layer, Service};
+
+use crate::request_id_from_extensions;
+
+/// This is a `tower` layer that records every resolved request id, for integration tests to inspect afterwards.
+///
+/// It must be applied inside a `RequestIdLayer`, since it reads the `ResolvedRequestId` extension.
+/// A request without a resolved request id isn't recorded.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{CapturingLayer, RequestIdConfig, RequestIdLayer};
+///
+/// let capturing = CapturingLayer::new();
+/// let captured = capturing.captured();
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(|| async {}))
+///     .layer(capturing)
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+///
+/// assert!(captured.lock().unwrap().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CapturingLayer {
+    captured: Arc<Mutex<Vec<String>>>,
+}
+
+impl CapturingLayer {
+    /// Creates the layer, with no request id recorded yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the request ids recorded so far, in the order the requests reached the layer, shared with the layer.
+    pub fn captured(&self) -> Arc<Mutex<Vec<String>>> {
+        self.captured.clone()
+    }
+}
+
+impl<S> Layer<S> for CapturingLayer {
+    type Service = CapturingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CapturingService {
+            inner,
+            captured: self.captured.clone(),
+        }
+    }
+}
+
+/// The service produced by `CapturingLayer`.
+#[derive(Debug, Clone)]
+pub struct CapturingService<S> {
+    inner: S,
+    captured: Arc<Mutex<Vec<String>>>,
+}
+
+impl<S> Service<Request> for CapturingService<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if let Some(request_id) = request_id_from_extensions(request.extensions()) {
+            // A panic while holding the lock must not hide the ids recorded so far.
+            self.captured
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(request_id.to_string());
+        }
+
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CapturingLayer, RequestIdConfig, RequestIdLayer};
+    use axum::{Router, body::Body, http::Request, routing::get};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_capture_resolved_ids() {
+        let capturing = CapturingLayer::new();
+        let captured = capturing.captured();
+
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(capturing)
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()["x-request-id"],
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *captured.lock().unwrap(),
+            vec![
+                "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                response.headers()["x-request-id"]
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            ]
+        );
+    }
+}
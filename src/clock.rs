@@ -0,0 +1,157 @@
+use std::time::SystemTime;
+
+/// The source of the current time of the time-based validations, e.g. `RequestIdConfig::with_max_clock_skew` or `RequestIdConfig::with_max_id_age`.
+///
+/// It's injected via `RequestIdConfig::with_clock`, e.g. to drive these validations deterministically in tests.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::{
+///     sync::Arc,
+///     time::{Duration, SystemTime, UNIX_EPOCH},
+/// };
+///
+/// use request_id_middleware::{Clock, RequestIdConfig};
+///
+/// struct FixedClock(SystemTime);
+///
+/// impl Clock for FixedClock {
+///     fn now(&self) -> SystemTime {
+///         self.0
+///     }
+/// }
+///
+/// let config = RequestIdConfig::default()
+///     .with_clock(Arc::new(FixedClock(UNIX_EPOCH + Duration::from_secs(1_745_239_734))));
+/// ```
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock, i.e. `SystemTime::now()` (default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use crate::{Clock, RequestIdConfig, RequestIdError, resolve::validate};
+
+    /// A clock that only moves when told to.
+    struct MockClock(Mutex<SystemTime>);
+
+    impl MockClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_clock_drives_skew() {
+        // The request id was generated at 1_745_239_734_443 ms.
+        let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+        let clock = Arc::new(MockClock(Mutex::new(
+            UNIX_EPOCH + Duration::from_millis(1_745_239_734_443) - Duration::from_secs(10),
+        )));
+
+        let config = RequestIdConfig::default()
+            .with_clock(clock.clone())
+            .with_max_clock_skew(Duration::from_secs(5));
+
+        assert_eq!(
+            validate(request_id, &config),
+            Err(RequestIdError::ClockSkew)
+        );
+
+        clock.advance(Duration::from_secs(5));
+
+        assert_eq!(validate(request_id, &config), Ok(request_id.to_string()));
+    }
+
+    #[test]
+    fn test_clock_drives_max_id_age() {
+        // The request id was generated at 1_745_239_734_443 ms.
+        let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+        let clock = Arc::new(MockClock(Mutex::new(
+            UNIX_EPOCH + Duration::from_millis(1_745_239_734_443) + Duration::from_secs(30),
+        )));
+
+        let config = RequestIdConfig::default()
+            .with_clock(clock.clone())
+            .with_max_id_age(Duration::from_secs(60));
+
+        assert_eq!(validate(request_id, &config), Ok(request_id.to_string()));
+
+        clock.advance(Duration::from_secs(31));
+
+        assert_eq!(validate(request_id, &config), Err(RequestIdError::Expired));
+        assert_eq!(
+            validate(request_id, &RequestIdConfig::default().with_clock(clock)),
+            Ok(request_id.to_string())
+        );
+    }
+
+    #[test]
+    fn test_clock_max_clock_skew_overflow() {
+        let config = RequestIdConfig::default().with_max_clock_skew(Duration::MAX);
+
+        assert_eq!(
+            validate("01965864-f8ab-7eb8-912a-a2c999ab110e", &config),
+            Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+        );
+    }
+
+    #[test]
+    fn test_clock_max_id_age_overflow() {
+        let config = RequestIdConfig::default().with_max_id_age(Duration::MAX);
+
+        assert_eq!(
+            validate("01965864-f8ab-7eb8-912a-a2c999ab110e", &config),
+            Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+        );
+    }
+
+    #[test]
+    fn test_clock_skew_hook() {
+        let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+        let clock = Arc::new(MockClock(Mutex::new(
+            UNIX_EPOCH + Duration::from_millis(1_745_239_734_443) - Duration::from_secs(3),
+        )));
+        let skews = Arc::new(Mutex::new(Vec::new()));
+
+        let config = RequestIdConfig::default()
+            .with_clock(clock.clone())
+            .with_on_clock_skew({
+                let skews = skews.clone();
+                move |skew| skews.lock().unwrap().push(skew)
+            });
+
+        validate(request_id, &config).unwrap();
+        clock.advance(Duration::from_secs(45));
+        validate(request_id, &config).unwrap();
+
+        let skews = skews.lock().unwrap();
+
+        assert_eq!(skews.len(), 2);
+        assert!((skews[0] + 3.0).abs() < 0.001, "{}", skews[0]);
+        assert!((skews[1] - 42.0).abs() < 0.001, "{}", skews[1]);
+    }
+}
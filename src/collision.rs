@@ -0,0 +1,153 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use log::error;
+use uuid::Uuid;
+
+use crate::Redaction;
+
+/// A detector of the collisions between generated request ids, e.g. to catch a broken RNG or clock in tests.
+///
+/// It's set via `RequestIdConfig::with_collision_checker`: each generated request id is then checked against the last
+/// `capacity` ones, and a collision is logged and counted. The ids sent by the client aren't checked.
+///
+/// It's meant for debugging and testing, since every generation briefly takes a lock.
+/// It's cheap to clone, and all the clones share the recorded ids, so a test keeps one to assert on `collisions`.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{CollisionChecker, RequestIdConfig};
+///
+/// let checker = CollisionChecker::new(10_000);
+///
+/// let config = RequestIdConfig::default().with_collision_checker(checker.clone());
+///
+/// assert_eq!(checker.collisions(), 0);
+/// ```
+#[derive(Clone)]
+pub struct CollisionChecker {
+    capacity: usize,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    seen: HashSet<Uuid>,
+    order: VecDeque<Uuid>,
+    collisions: usize,
+}
+
+impl CollisionChecker {
+    /// Creates the checker, remembering at most the last `capacity` generated ids.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Returns the number of collisions detected so far.
+    pub fn collisions(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .collisions
+    }
+
+    /// Records a generated id, and returns whether it collides with one of the remembered ids, logged per the given redaction.
+    pub(crate) fn check(&self, uuid: &Uuid, redaction: Redaction) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if state.seen.contains(uuid) {
+            state.collisions += 1;
+
+            error!(
+                "Generated request id {} collides with a previous one",
+                redaction.redact(&uuid.to_string())
+            );
+
+            return true;
+        }
+
+        if self.capacity == 0 {
+            return false;
+        }
+
+        if state.order.len() == self.capacity
+            && let Some(oldest) = state.order.pop_front()
+        {
+            state.seen.remove(&oldest);
+        }
+
+        state.seen.insert(*uuid);
+        state.order.push_back(*uuid);
+
+        false
+    }
+}
+
+impl fmt::Debug for CollisionChecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CollisionChecker")
+            .field("capacity", &self.capacity)
+            .field("collisions", &self.collisions())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        CollisionChecker, GenerationStrategy, GeneratorHandle, Redaction, RequestIdConfig,
+        validate_or_generate,
+    };
+    use uuid::Uuid;
+
+    #[test]
+    fn test_collision_stress() {
+        let checker = CollisionChecker::new(100_000);
+        let config = RequestIdConfig::default().with_collision_checker(checker.clone());
+
+        for _ in 0..100_000 {
+            validate_or_generate(None, &config).unwrap();
+        }
+
+        assert_eq!(checker.collisions(), 0);
+    }
+
+    #[test]
+    fn test_collision_detected() {
+        let checker = CollisionChecker::new(2);
+        let config = RequestIdConfig::default()
+            .with_collision_checker(checker.clone())
+            .with_generator_handle(GeneratorHandle::new(GenerationStrategy::External(
+                Arc::new(|| Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()),
+            )));
+
+        for _ in 0..3 {
+            validate_or_generate(None, &config).unwrap();
+        }
+
+        assert_eq!(checker.collisions(), 2);
+    }
+
+    #[test]
+    fn test_collision_bounded() {
+        let checker = CollisionChecker::new(2);
+        let [first, second, third] = [Uuid::now_v7(), Uuid::now_v7(), Uuid::now_v7()];
+
+        assert!(!checker.check(&first, Redaction::None));
+        assert!(!checker.check(&second, Redaction::None));
+        assert!(!checker.check(&third, Redaction::None));
+        assert!(checker.check(&third, Redaction::None));
+
+        // The first id was forgotten once the third one was recorded.
+        assert!(!checker.check(&first, Redaction::None));
+    }
+}
@@ -0,0 +1,1907 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt,
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use axum::http::{HeaderName, StatusCode, request::Parts};
+use uuid::{Uuid, Version};
+
+use crate::{
+    CachedIdFetcher, CanonicalForm, Clock, CollisionChecker, GeneratorHandle, IdPool, IdSource,
+    IdTemplate, LetterCase, Redaction, RejectionRenderer, RequestId, RequestIdError,
+    ResolutionEvent, ResolvedRequestId, SourceSpec, SystemClock, resolve::default_sources_of,
+};
+
+/// Configuration shared by the extractor and the layer.
+///
+/// The default configuration reads and echoes the `X-Request-Id` header, validates that the value is an UUID v7
+/// and generates a new UUID v7 when the header is missing.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::HeaderName;
+/// use request_id_middleware::RequestIdConfig;
+///
+/// let config = RequestIdConfig::default()
+///     .with_header_name(HeaderName::from_static("x-correlation-id"))
+///     .with_echo_response(false);
+///
+/// assert_eq!(config.header_name(), "x-correlation-id");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestIdConfig {
+    header_name: HeaderName,
+    echo_response: bool,
+    reject_zero_timestamp: bool,
+    random_bits_hook: Option<Callback<RandomBitsHook>>,
+    read_trailers: bool,
+    echo_handler_version: bool,
+    normalize_case: LetterCase,
+    canonical_form: Option<CanonicalForm>,
+    echo_on: Option<Callback<EchoOn>>,
+    rejection_renderer: Option<Callback<dyn RejectionRenderer>>,
+    freshness_check: Option<Callback<FreshnessCheck>>,
+    sources: Option<Vec<SourceSpec>>,
+    monotonic: bool,
+    dry_run: bool,
+    id_fetcher: Option<Callback<IdFetcher>>,
+    id_fetcher_fallback: bool,
+    on_rejection: Option<Callback<OnRejection>>,
+    log_redaction: Redaction,
+    server_timing: bool,
+    message_provider: Option<Callback<MessageProvider>>,
+    any_version: bool,
+    rewrite_request_header: bool,
+    synthesize_traceparent: bool,
+    on_resolved: Option<Callback<OnResolved>>,
+    percent_decode: bool,
+    reserved_prefixes: Vec<String>,
+    allowed_versions: Cow<'static, [Version]>,
+    sampling_header: Option<HeaderName>,
+    sampled_default: bool,
+    id_pool: Option<IdPool>,
+    prefix_case_sensitive: bool,
+    response_extension: bool,
+    clock: Option<Callback<dyn Clock>>,
+    max_clock_skew: Option<Duration>,
+    require_exact_hyphenation: bool,
+    require_id: Option<Callback<RequireId>>,
+    echo_in_trailers: bool,
+    trust_level: TrustLevel,
+    generation_template: Option<IdTemplate>,
+    extension_vs_header: Precedence,
+    response_write_mode: WriteMode,
+    require_tls: bool,
+    on_clock_skew: Option<Callback<OnClockSkew>>,
+    shadow_header: Option<HeaderName>,
+    generate_transform: Option<Callback<GenerateTransform>>,
+    trusted_proxies: Vec<IpAddr>,
+    on_resolution: Option<Callback<OnResolution>>,
+    fallback_id: Option<String>,
+    multipart_field: Cow<'static, str>,
+    enforcement: Enforcement,
+    version_header: Option<HeaderName>,
+    append_to_location: bool,
+    max_len: Option<usize>,
+    status_for: Option<Callback<StatusFor>>,
+    nil_means_generate: bool,
+    strict_variant: bool,
+    log_fields: Vec<LogField>,
+    generator_handle: Option<GeneratorHandle>,
+    denied_ids: Option<HashSet<Uuid>>,
+    allowed_ids: Option<HashSet<Uuid>>,
+    collision_checker: Option<CollisionChecker>,
+    id_prefix: Option<String>,
+    reject_zero_random: bool,
+    max_id_age: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    trace_span: bool,
+    #[cfg(feature = "tracing")]
+    span_field: SpanField,
+    /// The fields set via the builder methods, so that `merge` layers them whatever their value.
+    set_fields: Vec<&'static str>,
+}
+
+/// How much the request ids sent by the client are trusted, see `RequestIdConfig::with_trust_level`.
+///
+/// The security model is that of a single validating edge : the services exposed to untrusted clients validate the request id fully,
+/// and forward it canonicalized, so the services behind them, on a trusted network segment, needn't validate it again.
+/// `Internal` must only be set on services unreachable by the clients, otherwise they can inject arbitrary request ids, e.g. to
+/// collide with other requests in the logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrustLevel {
+    /// The request id is fully validated, as an UUID v7 (default).
+    #[default]
+    Edge,
+    /// The request id is only checked for presence, i.e. non-empty, and control characters, then trimmed and its case normalized.
+    ///
+    /// It's not parsed, so any format is forwarded as is, e.g. an UUID v4.
+    Internal,
+}
+
+/// Which request id wins when a previous layer already resolved one, i.e. the `ResolvedRequestId` extension, and the configured header
+/// carries a different one, see `RequestIdConfig::with_extension_vs_header`.
+///
+/// The header is validated, and formatted per the configuration, before being compared to the extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precedence {
+    /// The request id of the extension is kept, and the header is ignored (default).
+    #[default]
+    ExtensionWins,
+    /// The request id of the header replaces the one of the extension.
+    HeaderWins,
+    /// The request is rejected with a 400 Bad Request.
+    RejectMismatch,
+}
+
+/// How the layer echoes the request id when the response already carries the header, see `RequestIdConfig::with_response_write_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// The header is replaced by the request id (default).
+    #[default]
+    Overwrite,
+    /// The request id is appended as another value of the header, forming a chain, e.g. `downstream-id, request-id` once combined.
+    AppendIfPresent,
+    /// The header is kept as is.
+    SkipIfPresent,
+}
+
+/// Whether invalid request ids are rejected, see `RequestIdConfig::with_enforcement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Enforcement {
+    /// Invalid request ids are rejected, e.g. with a 400 Bad Request (default).
+    #[default]
+    Enforce,
+    /// Invalid request ids, and missing required ones, are logged as warnings and replaced by a generated one.
+    WarnOnly,
+}
+
+/// A request field appended to the log line of each rejected request id, see `RequestIdConfig::with_log_fields`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogField {
+    /// The request method, e.g. `method=POST`.
+    Method,
+    /// The request path, without the query string, e.g. `path=/orders`.
+    Path,
+    /// The address of the immediate upstream, per the forwarded headers or the peer of the connection, e.g. `client_ip=203.0.113.43`.
+    ClientIp,
+    /// The `User-Agent` header, quoted, e.g. `user_agent="curl/8.7.1"`.
+    UserAgent,
+}
+
+/// The name of the `tracing` span field carrying the request id.
+///
+/// `tracing` field names are static, so only these names are supported.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpanField {
+    /// `request_id` (default).
+    #[default]
+    RequestId,
+    /// `http.request.id`, per the OpenTelemetry HTTP semantic conventions, e.g. for `axum-tracing-opentelemetry`.
+    HttpRequestId,
+    /// `req_id`, per the Bunyan conventions, e.g. for `tracing-bunyan-formatter`, which emits the span fields as top-level JSON keys.
+    ReqId,
+    /// `requestId`, per the camel case conventions of most JSON logging pipelines, e.g. on Node.js services sharing the same indexes.
+    RequestIdCamelCase,
+}
+
+/// A hook applied to the 10 entropy bytes of a generated UUID v7, before the version and variant bits are set.
+pub type RandomBitsHook = dyn Fn(&mut [u8]) + Send + Sync;
+
+/// A condition on the response status for the layer to echo the request id.
+pub type EchoOn = dyn Fn(StatusCode) -> bool + Send + Sync;
+
+/// An async check that a request id sent by the client has not been seen before.
+pub type FreshnessCheck =
+    dyn Fn(String) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync;
+
+/// A hook called on each rejected request id.
+pub type OnRejection = dyn Fn(&RequestIdError) + Send + Sync;
+
+/// A hook mapping each rejection reason to the HTTP status of the response.
+pub type StatusFor = dyn Fn(&RequestIdError) -> StatusCode + Send + Sync;
+
+/// A hook called with the clock skew of each UUID v7 sent by the client, in seconds.
+pub type OnClockSkew = dyn Fn(f64) + Send + Sync;
+
+/// A transform applied to each generated request id.
+pub type GenerateTransform = dyn Fn(String) -> String + Send + Sync;
+
+/// A hook called by the layer with the resolution event of each request it resolves.
+pub type OnResolution = dyn Fn(ResolutionEvent) + Send + Sync;
+
+/// A hook called by the layer on each request id it resolves.
+pub type OnResolved = dyn Fn(&ResolvedRequestId) + Send + Sync;
+
+/// A predicate deciding whether the request id is required for a given request.
+pub type RequireId = dyn Fn(&Parts) -> bool + Send + Sync;
+
+/// A provider of the rejection messages, given the error and the first language tag of the `Accept-Language` header.
+pub type MessageProvider = dyn Fn(&RequestIdError, Option<&str>) -> String + Send + Sync;
+
+/// An async fetcher of new request ids, e.g. from a central id service.
+pub type IdFetcher =
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<String, BoxError>> + Send>> + Send + Sync;
+
+/// The error returned by an `IdFetcher`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A shared callback, printed opaquely by `Debug`.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback(..)")
+    }
+}
+
+impl RequestIdConfig {
+    /// Creates the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the header used to read the request id, and to echo it on the response.
+    pub fn with_header_name(mut self, header_name: HeaderName) -> Self {
+        self.header_name = header_name;
+        self.mark_set("header_name");
+        self
+    }
+
+    /// Sets whether the layer echoes the resolved request id on the response (default `true`).
+    pub fn with_echo_response(mut self, echo_response: bool) -> Self {
+        self.echo_response = echo_response;
+        self.mark_set("echo_response");
+        self
+    }
+
+    /// Sets whether an UUID v7 with an all-zero timestamp is rejected (default `false`).
+    ///
+    /// Such an id is valid per version and variant, but usually comes from a fuzzer or a broken generator.
+    pub fn with_reject_zero_timestamp(mut self, reject_zero_timestamp: bool) -> Self {
+        self.reject_zero_timestamp = reject_zero_timestamp;
+        self.mark_set("reject_zero_timestamp");
+        self
+    }
+
+    /// Sets a hook applied to the entropy bytes of each generated UUID v7, e.g. to encode a shard id in the random bits.
+    ///
+    /// The hook receives the 10 bytes following the 48-bit timestamp.
+    /// The version (4 bits of the first byte) and the variant (2 bits of the third byte) are set afterwards, and override the hook.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_random_bits_hook(|bytes| bytes[9] = 0x2a);
+    /// ```
+    pub fn with_random_bits_hook(
+        mut self,
+        random_bits_hook: impl Fn(&mut [u8]) + Send + Sync + 'static,
+    ) -> Self {
+        self.random_bits_hook = Some(Callback(Arc::new(random_bits_hook)));
+        self.mark_set("random_bits_hook");
+        self
+    }
+
+    /// Sets whether the request id is read from the request trailers when the header is missing (default `false`).
+    ///
+    /// Axum doesn't expose trailers before the body is consumed, so they're only read from a `RequestTrailers`
+    /// inserted into the request extensions beforehand, e.g. by a proxy layer that buffered the body.
+    pub fn with_read_trailers(mut self, read_trailers: bool) -> Self {
+        self.read_trailers = read_trailers;
+        self.mark_set("read_trailers");
+        self
+    }
+
+    /// Sets whether the layer adds the `X-Request-Id-Handler` response header, carrying the crate version (default `false`).
+    pub fn with_echo_handler_version(mut self, echo_handler_version: bool) -> Self {
+        self.echo_handler_version = echo_handler_version;
+        self.mark_set("echo_handler_version");
+        self
+    }
+
+    /// Sets the letter case of the request id (default `LetterCase::Lower`).
+    pub fn with_normalize_case(mut self, normalize_case: LetterCase) -> Self {
+        self.normalize_case = normalize_case;
+        self.mark_set("normalize_case");
+        self
+    }
+
+    /// Sets the canonical form the request id is re-formatted into (default : none, the form sent by the client is kept).
+    ///
+    /// It combines with `with_normalize_case`, e.g. `CanonicalForm::Hyphenated` and `LetterCase::Upper` give `01965864-F8AB-7EB8-912A-A2C999AB110E`,
+    /// and `CanonicalForm::Simple` and `LetterCase::Upper` give `01965864F8AB7EB8912AA2C999AB110E`, for the sent and generated request ids alike.
+    pub fn with_canonical_form(mut self, canonical_form: CanonicalForm) -> Self {
+        self.canonical_form = Some(canonical_form);
+        self.mark_set("canonical_form");
+        self
+    }
+
+    /// Sets the condition on the response status for the layer to echo the request id (default : on all responses).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// // Echo on 2xx and 4xx responses, but not on 5xx ones.
+    /// let config = RequestIdConfig::default().with_echo_on(|status| !status.is_server_error());
+    /// ```
+    pub fn with_echo_on(
+        mut self,
+        echo_on: impl Fn(StatusCode) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.echo_on = Some(Callback(Arc::new(echo_on)));
+        self.mark_set("echo_on");
+        self
+    }
+
+    /// Sets the renderer turning a rejected request id into a response (default `TextRenderer`).
+    pub fn with_rejection_renderer(
+        mut self,
+        rejection_renderer: Arc<dyn RejectionRenderer>,
+    ) -> Self {
+        self.rejection_renderer = Some(Callback(rejection_renderer));
+        self.mark_set("rejection_renderer");
+        self
+    }
+
+    /// Sets an async check that a request id sent by the client has not been seen before, e.g. backed by Redis.
+    ///
+    /// If the check returns `false`, the request is rejected with a 409 Conflict. Generated request ids are not checked.
+    /// It's run by the layer and by the async extractor, but not by the sync `ExtractRequestId::from_parts`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default()
+    ///     .with_freshness_check(|request_id| Box::pin(async move { !request_id.is_empty() }));
+    /// ```
+    pub fn with_freshness_check(
+        mut self,
+        freshness_check: impl Fn(String) -> Pin<Box<dyn Future<Output = bool> + Send>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.freshness_check = Some(Callback(Arc::new(freshness_check)));
+        self.mark_set("freshness_check");
+        self
+    }
+
+    /// Sets the ordered sources the request id is read from, the first one carrying a value wins, and a new UUID v7 is generated when none does.
+    ///
+    /// By default, it's the configured header, followed by the request trailers if `with_read_trailers` is enabled.
+    /// When set, the sources replace that default chain, i.e. `with_header_name` only sets the echoed response header.
+    /// Each source is validated and normalized, see `with_source` to declare them one by one with their own settings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::{IdSource, RequestIdConfig};
+    ///
+    /// let config = RequestIdConfig::default().with_sources(vec![
+    ///     IdSource::Header("x-request-id".parse().unwrap()),
+    ///     IdSource::Cookie("request_id".to_string()),
+    ///     IdSource::Query("request_id".to_string()),
+    ///     IdSource::Traceparent,
+    /// ]);
+    /// ```
+    pub fn with_sources(mut self, sources: Vec<IdSource>) -> Self {
+        self.sources = Some(sources.into_iter().map(SourceSpec::from).collect());
+        self.mark_set("sources");
+        self
+    }
+
+    /// Appends a source to the ordered sources the request id is read from, see `with_sources`, with its own validation and normalization.
+    ///
+    /// The first call replaces the default chain, so the sources are declared one by one, in order, and a new UUID v7 is still generated
+    /// when none carries a value, unless `with_require_id` requires it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::http::HeaderName;
+    /// use request_id_middleware::{IdSource, RequestIdConfig};
+    ///
+    /// let config = RequestIdConfig::default()
+    ///     .with_source(IdSource::Header(HeaderName::from_static("x-request-id")).with_validate(true))
+    ///     .with_source(IdSource::Cookie("rid".to_string()).with_validate(false));
+    ///
+    /// assert_eq!(
+    ///     config.configured_sources(),
+    ///     vec!["header:x-request-id", "cookie:rid (unvalidated)", "generate"]
+    /// );
+    /// ```
+    pub fn with_source(mut self, source: impl Into<SourceSpec>) -> Self {
+        self.sources
+            .get_or_insert_with(Vec::new)
+            .push(source.into());
+        self.mark_set("sources");
+        self
+    }
+
+    /// Sets whether generated ids are strictly increasing within the process, even within the same millisecond (default `false`).
+    ///
+    /// They're generated from a counter reseeded each millisecond, so the random bits hook is not applied to them.
+    pub fn with_monotonic(mut self, monotonic: bool) -> Self {
+        self.monotonic = monotonic;
+        self.mark_set("monotonic");
+        self
+    }
+
+    /// Sets whether the layer accepts invalid request ids instead of rejecting them, e.g. to roll out the validation safely (default `false`).
+    ///
+    /// The invalid request id is replaced by a newly generated one, and the response carries the `X-Request-Id-Warning` header
+    /// describing what would have failed, e.g. `not-a-v7`. The extractors alone still reject invalid request ids.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self.mark_set("dry_run");
+        self
+    }
+
+    /// Sets an async fetcher of new request ids, e.g. from a central id service, used by the layer instead of the local generation.
+    ///
+    /// The fetched request id is trusted, i.e. not validated. If the fetch fails, the layer falls back to a locally generated
+    /// UUID v7, unless disabled via `with_id_fetcher_fallback`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    /// use uuid::Uuid;
+    ///
+    /// let config = RequestIdConfig::default()
+    ///     .with_id_fetcher(|| Box::pin(async { Ok(Uuid::now_v7().to_string()) }));
+    /// ```
+    pub fn with_id_fetcher(
+        mut self,
+        id_fetcher: impl Fn() -> Pin<Box<dyn Future<Output = Result<String, BoxError>> + Send>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.id_fetcher = Some(Callback(Arc::new(id_fetcher)));
+        self.mark_set("id_fetcher");
+        self
+    }
+
+    /// Sets a cache of request ids fetched by blocks as the id fetcher, see `CachedIdFetcher`.
+    pub fn with_cached_id_fetcher(self, cached_id_fetcher: CachedIdFetcher) -> Self {
+        self.with_id_fetcher(move || {
+            let cached_id_fetcher = cached_id_fetcher.clone();
+
+            Box::pin(async move { cached_id_fetcher.fetch().await })
+        })
+    }
+
+    /// Sets whether the layer falls back to a locally generated UUID v7 when the id fetcher fails (default `true`).
+    ///
+    /// Otherwise, the request is rejected with a 503 Service Unavailable.
+    pub fn with_id_fetcher_fallback(mut self, id_fetcher_fallback: bool) -> Self {
+        self.id_fetcher_fallback = id_fetcher_fallback;
+        self.mark_set("id_fetcher_fallback");
+        self
+    }
+
+    /// Sets a hook called on each rejected request id, e.g. to count the rejections by reason in a metrics system.
+    ///
+    /// The reason can be used as a label via `RequestIdError::code`, e.g. `request_id_rejections_total{reason="not-a-uuid"}`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// static REJECTIONS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// let config = RequestIdConfig::default().with_on_rejection(|error| {
+    ///     REJECTIONS.fetch_add(1, Ordering::Relaxed);
+    ///     println!("request_id_rejections_total{{reason=\"{}\"}}", error.code());
+    /// });
+    /// ```
+    pub fn with_on_rejection(
+        mut self,
+        on_rejection: impl Fn(&RequestIdError) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_rejection = Some(Callback(Arc::new(on_rejection)));
+        self.mark_set("on_rejection");
+        self
+    }
+
+    /// Sets how the request id appears in the crate's own log and trace output (default `Redaction::None`).
+    pub fn with_log_redaction(mut self, log_redaction: Redaction) -> Self {
+        self.log_redaction = log_redaction;
+        self.mark_set("log_redaction");
+        self
+    }
+
+    /// Sets whether the layer also echoes the request id on the `Server-Timing` response header, as `reqid;desc="<id>"` (default `false`).
+    ///
+    /// It's independent from `with_echo_response`, so disabling the latter echoes it only on `Server-Timing`.
+    /// Browser devtools display it along with the other timing metrics.
+    pub fn with_server_timing(mut self, server_timing: bool) -> Self {
+        self.server_timing = server_timing;
+        self.mark_set("server_timing");
+        self
+    }
+
+    /// Sets a provider of the rejection messages, e.g. to translate them per the `Accept-Language` request header.
+    ///
+    /// It's given the error and the first language tag of the `Accept-Language` header, if any, e.g. `fr-FR`.
+    /// By default, the messages are in English, e.g. `Invalid X-Request-Id : Not a valid UUID`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::{RequestIdConfig, RequestIdError};
+    ///
+    /// let config = RequestIdConfig::default().with_message_provider(|error, language| {
+    ///     match (error, language) {
+    ///         (RequestIdError::NotUuid, Some(language)) if language.starts_with("fr") => {
+    ///             "X-Request-Id invalide : UUID non valide".to_string()
+    ///         }
+    ///         _ => error.to_string(),
+    ///     }
+    /// });
+    /// ```
+    pub fn with_message_provider(
+        mut self,
+        message_provider: impl Fn(&RequestIdError, Option<&str>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.message_provider = Some(Callback(Arc::new(message_provider)));
+        self.mark_set("message_provider");
+        self
+    }
+
+    /// Sets whether any UUID version is accepted, e.g. by `ExtractRequestIdGraded`.
+    pub(crate) fn with_any_version(mut self, any_version: bool) -> Self {
+        self.any_version = any_version;
+        self.mark_set("any_version");
+        self
+    }
+
+    /// Sets whether the layer rewrites the request header to the resolved request id, before calling the inner service (default `false`).
+    ///
+    /// Handlers reading `parts.headers` directly then see the canonical, or generated, request id.
+    pub fn with_rewrite_request_header(mut self, rewrite_request_header: bool) -> Self {
+        self.rewrite_request_header = rewrite_request_header;
+        self.mark_set("rewrite_request_header");
+        self
+    }
+
+    /// Sets whether the layer injects a W3C `traceparent` request header derived from a generated request id, if the request has none (default `false`).
+    ///
+    /// Its trace id is the request id, and its parent id the last 8 bytes of the request id, e.g.
+    /// `00-01965864f8ab7eb8912aa2c999ab110e-912aa2c999ab110e-01`, so that downstream services get a consistent trace context.
+    pub fn with_synthesize_traceparent(mut self, synthesize_traceparent: bool) -> Self {
+        self.synthesize_traceparent = synthesize_traceparent;
+        self.mark_set("synthesize_traceparent");
+        self
+    }
+
+    /// Sets a hook called by the layer on each request id it resolves, before calling the inner service, e.g. for audit logging.
+    ///
+    /// It's not called by a layer reusing the request id resolved by a previous one, nor by the extractors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_on_resolved(|resolved| {
+    ///     println!("Resolved Request Id: {} (generated : {})", resolved.id, resolved.generated);
+    /// });
+    /// ```
+    pub fn with_on_resolved(
+        mut self,
+        on_resolved: impl Fn(&ResolvedRequestId) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_resolved = Some(Callback(Arc::new(on_resolved)));
+        self.mark_set("on_resolved");
+        self
+    }
+
+    /// Sets whether the request id is percent-decoded before its validation, e.g. `01965864%2Df8ab...` sent by misbehaving clients (default `false`).
+    ///
+    /// It's decoded once, and it must still be a valid UUID v7 afterwards. Still, keep it disabled unless needed:
+    /// a proxy or a WAF checking the raw header value upstream doesn't see the value that is actually accepted.
+    pub fn with_percent_decode(mut self, percent_decode: bool) -> Self {
+        self.percent_decode = percent_decode;
+        self.mark_set("percent_decode");
+        self
+    }
+
+    /// Sets the prefixes reserved to internal tooling, e.g. `internal-`, that the request ids sent by the client must not start with (default none).
+    ///
+    /// Such request ids are rejected with a 403 Forbidden. Generated and fetched request ids are not checked.
+    pub fn with_reserved_prefixes(mut self, reserved_prefixes: Vec<String>) -> Self {
+        self.reserved_prefixes = reserved_prefixes;
+        self.mark_set("reserved_prefixes");
+        self
+    }
+
+    /// Sets the UUID versions accepted from the client (default only `Version::SortRand`, i.e. UUIDs v7).
+    ///
+    /// It lets deployments accept e.g. name-based UUIDs v5 as well, see `RequestId::namespace_bits`.
+    /// Generated request ids are UUIDs v7 regardless.
+    pub fn with_allowed_versions(mut self, allowed_versions: Vec<Version>) -> Self {
+        self.allowed_versions = Cow::Owned(allowed_versions);
+        self.mark_set("allowed_versions");
+        self
+    }
+
+    /// Sets the header carrying the trace-sampling decision paired with the request id, e.g. `X-Sampled: 1` (default none).
+    ///
+    /// It's exposed by `ExtractRequestIdSampled`, and echoed on the response by the layer.
+    /// The values `1` and `true` mean sampled, `0` and `false` not sampled, anything else falls back to `with_sampled_default`.
+    pub fn with_sampling_header(mut self, sampling_header: HeaderName) -> Self {
+        self.sampling_header = Some(sampling_header);
+        self.mark_set("sampling_header");
+        self
+    }
+
+    /// Sets the trace-sampling decision when the sampling header is missing or invalid (default `false`).
+    pub fn with_sampled_default(mut self, sampled_default: bool) -> Self {
+        self.sampled_default = sampled_default;
+        self.mark_set("sampled_default");
+        self
+    }
+
+    /// Sets a pool of pre-generated ids, popped for each missing request id before generating one (default none).
+    ///
+    /// The pooled ids bypass the random bits hook.
+    pub fn with_id_pool(mut self, id_pool: IdPool) -> Self {
+        self.id_pool = Some(id_pool);
+        self.mark_set("id_pool");
+        self
+    }
+
+    /// Sets whether the prefix-based checks, i.e. `with_reserved_prefixes`, match case-sensitively (default `true`).
+    pub fn with_prefix_case_sensitive(mut self, prefix_case_sensitive: bool) -> Self {
+        self.prefix_case_sensitive = prefix_case_sensitive;
+        self.mark_set("prefix_case_sensitive");
+        self
+    }
+
+    /// Sets whether the layer also inserts the `ResolvedRequestId` into the response extensions, e.g. for a reverse proxy forwarding it (default `false`).
+    ///
+    /// It also lets the integration tests driving the app with `oneshot` assert on the resolved request id without parsing the headers.
+    pub fn with_response_extension(mut self, response_extension: bool) -> Self {
+        self.response_extension = response_extension;
+        self.mark_set("response_extension");
+        self
+    }
+
+    /// Sets the source of the current time of the time-based validations (default `SystemClock`).
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(Callback(clock));
+        self.mark_set("clock");
+        self
+    }
+
+    /// Sets how far in the future, per the configured clock, the timestamp of an UUID v7 sent by the client may be (default unlimited).
+    ///
+    /// A request id further in the future is rejected, since it was generated by a client with a skewed clock.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = Some(max_clock_skew);
+        self.mark_set("max_clock_skew");
+        self
+    }
+
+    /// Sets whether the request ids sent by the client must be exactly hyphenated, i.e. 36 characters with hyphens at the 8, 13, 18 and 23 positions (default `false`).
+    ///
+    /// Otherwise, the simple, braced and URN forms are accepted as well. It's checked before parsing, and rejected as `RequestIdError::NotUuid`.
+    pub fn with_require_exact_hyphenation(mut self, require_exact_hyphenation: bool) -> Self {
+        self.require_exact_hyphenation = require_exact_hyphenation;
+        self.mark_set("require_exact_hyphenation");
+        self
+    }
+
+    /// Sets a predicate deciding whether the request id is required for a given request, e.g. for mutations needing it for idempotency (default none, i.e. never).
+    ///
+    /// When it returns `true` and no source carries a request id, the request is rejected with a 400 Bad Request instead of generating one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::http::Method;
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_require_id(|parts| parts.method != Method::GET);
+    /// ```
+    pub fn with_require_id(
+        mut self,
+        require_id: impl Fn(&Parts) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.require_id = Some(Callback(Arc::new(require_id)));
+        self.mark_set("require_id");
+        self
+    }
+
+    /// Sets whether the layer also echoes the request id as a response trailer, once the body is streamed (default `false`).
+    ///
+    /// It's independent from `with_echo_response`, so disabling the latter echoes it only in the trailers, and it obeys `with_echo_on`.
+    /// Trailers are only delivered over HTTP/2, and over HTTP/1.1 with a chunked body to clients sending `TE: trailers` ;
+    /// most browsers and many HTTP/1.1 clients and proxies drop them, so it's best paired with the leading header.
+    pub fn with_echo_in_trailers(mut self, echo_in_trailers: bool) -> Self {
+        self.echo_in_trailers = echo_in_trailers;
+        self.mark_set("echo_in_trailers");
+        self
+    }
+
+    /// Sets how much the request ids sent by the client are trusted, i.e. how they're validated (default `TrustLevel::Edge`).
+    pub fn with_trust_level(mut self, trust_level: TrustLevel) -> Self {
+        self.trust_level = trust_level;
+        self.mark_set("trust_level");
+        self
+    }
+
+    /// Sets the template wrapping the generated request ids, e.g. `req_{uuid}` (default `None`).
+    ///
+    /// The request ids sent by the client must then match the template, and only its `{uuid}` placeholder is validated,
+    /// so that the request ids generated by another service sharing the template are accepted. Since they are not UUIDs,
+    /// the typed extractors, e.g. `RequestId`, reject them.
+    pub fn with_generation_template(mut self, generation_template: IdTemplate) -> Self {
+        self.generation_template = Some(generation_template);
+        self.mark_set("generation_template");
+        self
+    }
+
+    /// Sets which request id wins when a previous layer already resolved one, and the configured header carries a different one (default `Precedence::ExtensionWins`).
+    pub fn with_extension_vs_header(mut self, extension_vs_header: Precedence) -> Self {
+        self.extension_vs_header = extension_vs_header;
+        self.mark_set("extension_vs_header");
+        self
+    }
+
+    /// Sets how the layer echoes the request id when the response already carries the header, e.g. set by a proxied downstream service (default `WriteMode::Overwrite`).
+    pub fn with_response_write_mode(mut self, response_write_mode: WriteMode) -> Self {
+        self.response_write_mode = response_write_mode;
+        self.mark_set("response_write_mode");
+        self
+    }
+
+    /// Sets whether the request ids sent by the client are only trusted over HTTPS, and ignored in favor of a generated one otherwise (default `false`).
+    ///
+    /// HTTPS is detected from the `X-Forwarded-Proto` header, then the `proto` of the `Forwarded` header, then the scheme of the request URI.
+    /// The forwarded headers are set by the client itself when there's no proxy, so this is only sound behind a reverse proxy
+    /// terminating TLS that overwrites them, i.e. that never forwards the ones sent by the client.
+    pub fn with_require_tls(mut self, require_tls: bool) -> Self {
+        self.require_tls = require_tls;
+        self.mark_set("require_tls");
+        self
+    }
+
+    /// Sets a hook called with the clock skew of each UUID v7 sent by the client, i.e. the current time per the configured clock
+    /// minus the timestamp of the UUID, in seconds, e.g. to record a `request_id_clock_skew_seconds` histogram in a metrics system.
+    ///
+    /// The skew is negative for a timestamp in the future. Across the clients, its distribution reveals the health of their clocks.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_on_clock_skew(|skew| {
+    ///     println!("request_id_clock_skew_seconds {}", skew);
+    /// });
+    /// ```
+    pub fn with_on_clock_skew(
+        mut self,
+        on_clock_skew: impl Fn(f64) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_clock_skew = Some(Callback(Arc::new(on_clock_skew)));
+        self.mark_set("on_clock_skew");
+        self
+    }
+
+    /// Sets a shadow response header the layer always sets to a fresh UUID v7, e.g. `x-request-id-v7`, during a migration to another id scheme (default `None`).
+    ///
+    /// The configured header keeps carrying the request id sent by the client, e.g. in a legacy format accepted via
+    /// `RequestIdConfig::with_sources`, so that both schemes can be correlated in the logs until the migration completes.
+    pub fn with_shadow_header(mut self, shadow_header: HeaderName) -> Self {
+        self.shadow_header = Some(shadow_header);
+        self.mark_set("shadow_header");
+        self
+    }
+
+    /// Sets a transform applied to each generated request id, after the template if any, e.g. to add a region prefix.
+    ///
+    /// The transformed request id is the one returned by the extractors and echoed by the layer. It isn't validated,
+    /// so when it's sent back, e.g. by another service of the same fleet, it's rejected unless it still validates,
+    /// e.g. per `RequestIdConfig::with_generation_template` for a prefix, or `TrustLevel::Internal`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_generate_transform(|request_id| request_id.to_uppercase());
+    /// ```
+    pub fn with_generate_transform(
+        mut self,
+        generate_transform: impl Fn(String) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.generate_transform = Some(Callback(Arc::new(generate_transform)));
+        self.mark_set("generate_transform");
+        self
+    }
+
+    /// Sets the proxies the request ids sent by the client are trusted from, and ignored in favor of a generated one otherwise (default none, i.e. trusted from any client).
+    ///
+    /// The immediate upstream is the last hop of the `X-Forwarded-For` header, then of the `Forwarded` header, i.e. the client
+    /// of the proxy closest to the service, or the peer of the connection, per the `ConnectInfo<SocketAddr>` extension, when there's no chain.
+    /// It's only sound when each trusted proxy appends the address of its own client to the chain.
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self.mark_set("trusted_proxies");
+        self
+    }
+
+    /// Sets a hook called by the layer with a structured event per request it resolves, i.e. the source, outcome, reason and latency,
+    /// e.g. to feed a custom observability backend.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_on_resolution(|event| {
+    ///     println!("{:?} in {:?}", event.outcome, event.latency);
+    /// });
+    /// ```
+    pub fn with_on_resolution(
+        mut self,
+        on_resolution: impl Fn(ResolutionEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_resolution = Some(Callback(Arc::new(on_resolution)));
+        self.mark_set("on_resolution");
+        self
+    }
+
+    /// Sets a constant request id used when no source carries one and `with_require_id` requires it, instead of rejecting the request (default none).
+    ///
+    /// It's a `RequestId`, so it's validated as an UUID v7 when building the configuration, and it's returned as is, i.e. not generated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::{RequestId, RequestIdConfig};
+    /// use uuid::Uuid;
+    ///
+    /// let fallback_id = RequestId::try_from_uuid(Uuid::from_u128(0x7000_8000_0000_0000_0000)).unwrap();
+    ///
+    /// let config = RequestIdConfig::default()
+    ///     .with_require_id(|_| true)
+    ///     .with_fallback_id(fallback_id);
+    ///
+    /// assert_eq!(config.fallback_id(), Some("00000000-0000-7000-8000-000000000000"));
+    /// ```
+    pub fn with_fallback_id(mut self, fallback_id: RequestId) -> Self {
+        self.fallback_id = Some(fallback_id.to_string());
+        self.mark_set("fallback_id");
+        self
+    }
+
+    /// Sets the name of the multipart form field read by `ExtractMultipartRequestId`, when no source carries a request id (default `request_id`).
+    ///
+    /// It's meant for HTML forms, which can't set headers, e.g. `<input type="hidden" name="request_id">`.
+    pub fn with_multipart_field(mut self, multipart_field: &str) -> Self {
+        self.multipart_field = Cow::Owned(multipart_field.to_string());
+        self.mark_set("multipart_field");
+        self
+    }
+
+    /// Sets whether invalid request ids are rejected, or only logged and replaced by a generated one, e.g. for local development (default `Enforcement::Enforce`).
+    ///
+    /// Unlike `with_dry_run`, it applies to the extractors as well, and it doesn't add any response header.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::{Enforcement, RequestIdConfig};
+    ///
+    /// let enforcement = if cfg!(debug_assertions) {
+    ///     Enforcement::WarnOnly
+    /// } else {
+    ///     Enforcement::Enforce
+    /// };
+    ///
+    /// let config = RequestIdConfig::default().with_enforcement(enforcement);
+    /// ```
+    pub fn with_enforcement(mut self, enforcement: Enforcement) -> Self {
+        self.enforcement = enforcement;
+        self.mark_set("enforcement");
+        self
+    }
+
+    /// Sets the header negotiating the format of the request id per request, e.g. `X-Request-Id-Version` (default none).
+    ///
+    /// Its value, case-insensitive, selects how the request id sent by the client is validated :
+    /// - `v7` : as an UUID v7, per the configuration, like without the header ;
+    /// - `ulid` : as a ULID, i.e. 26 Crockford base32 characters, uppercased.
+    ///
+    /// The header is chosen by the client, so a ULID still goes through the checks that don't depend on the format, i.e. the maximum length,
+    /// the id prefix, the control characters, the reserved prefixes, and the denylist and allowlist, applied to its 128 bits.
+    ///
+    /// Any other value is rejected as `RequestIdError::UnknownVersion`, and a missing header falls back to the configured validation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_version_header("x-request-id-version".parse().unwrap());
+    /// ```
+    pub fn with_version_header(mut self, version_header: HeaderName) -> Self {
+        self.version_header = Some(version_header);
+        self.mark_set("version_header");
+        self
+    }
+
+    /// Sets whether the layer appends the request id to the query string of the `Location` header of the 3xx responses (default `false`).
+    ///
+    /// The parameter is named after the configured header, e.g. `/login?x-request-id=...`, so that the request following the redirect
+    /// carries the request id even when the client doesn't forward the header, e.g. a browser. Add that parameter as an
+    /// `IdSource::Query` source to read it back, and mind that, unlike a header, it may end up in logs of third parties.
+    pub fn with_append_to_location(mut self, append_to_location: bool) -> Self {
+        self.append_to_location = append_to_location;
+        self.mark_set("append_to_location");
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of the request ids sent by the client (default none, i.e. only the UUID lengths are checked).
+    ///
+    /// A longer request id is rejected as `RequestIdError::TooLong` before it's validated, whatever the format negotiated per
+    /// `with_version_header`, e.g. percent-decoded per `with_percent_decode` or normalized. It applies to the value read from the source: an `IdSource::Query` parameter is already percent-decoded
+    /// and the `jti` claim of an `IdSource::JwtId` token already decoded by then, as they're bounded by the request limits anyway.
+    /// It matters for the request ids that aren't parsed as UUIDs, per `with_trust_level`, `with_generation_template`
+    /// or `IdSource::AmznRequestId`, since the UUID lengths are checked before parsing anyway.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self.mark_set("max_len");
+        self
+    }
+
+    /// Sets a hook mapping each rejection reason to the HTTP status of the response (default none, i.e. `RequestIdError::status`).
+    ///
+    /// It applies whatever the renderer, e.g. to reject a missing request id with a 428 Precondition Required,
+    /// or a wrong version with a 422 Unprocessable Entity.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::http::StatusCode;
+    /// use request_id_middleware::{RequestIdConfig, RequestIdError};
+    ///
+    /// let config = RequestIdConfig::default().with_status_for(|error| match error {
+    ///     RequestIdError::NotV7 => StatusCode::UNPROCESSABLE_ENTITY,
+    ///     error => error.status(),
+    /// });
+    /// ```
+    pub fn with_status_for(
+        mut self,
+        status_for: impl Fn(&RequestIdError) -> StatusCode + Send + Sync + 'static,
+    ) -> Self {
+        self.status_for = Some(Callback(Arc::new(status_for)));
+        self.mark_set("status_for");
+        self
+    }
+
+    /// Sets whether a nil UUID sent by the client, i.e. `00000000-0000-0000-0000-000000000000` in any form, is handled as a missing request id,
+    /// e.g. for clients sending it to ask for a new one (default `false`, i.e. it's rejected as `RequestIdError::NotV7`).
+    ///
+    /// It takes precedence over the validation, so the nil UUID is neither checked against `with_allowed_versions` nor `with_reject_zero_timestamp`.
+    /// The next sources are tried, then a new request id is generated, unless `with_require_id` requires it.
+    pub fn with_nil_means_generate(mut self, nil_means_generate: bool) -> Self {
+        self.nil_means_generate = nil_means_generate;
+        self.mark_set("nil_means_generate");
+        self
+    }
+
+    /// Sets whether the variant of the UUIDs sent by the client is checked together with their version, per RFC 9562 (default `false`).
+    ///
+    /// The versions 1 to 8 are only defined for the RFC 9562 variant, i.e. `10` in the two upper bits of the ninth byte,
+    /// so an UUID whose version nibble says 7 but with another variant is rejected as `RequestIdError::InvalidVariant`.
+    /// Otherwise, only the version nibble is checked.
+    pub fn with_strict_variant(mut self, strict_variant: bool) -> Self {
+        self.strict_variant = strict_variant;
+        self.mark_set("strict_variant");
+        self
+    }
+
+    /// Sets the request fields appended to the log line of each rejected request id, e.g. to trace a misbehaving client (default none).
+    ///
+    /// When set, a rejection is logged as an error along with the selected fields, in order, e.g.
+    /// `[x-request-id] Rejected request id due to : Not an UUID v7 (method=POST path=/orders client_ip=203.0.113.43)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::{LogField, RequestIdConfig};
+    ///
+    /// let config = RequestIdConfig::default().with_log_fields(vec![LogField::Method, LogField::Path, LogField::ClientIp]);
+    /// ```
+    pub fn with_log_fields(mut self, log_fields: Vec<LogField>) -> Self {
+        self.log_fields = log_fields;
+        self.mark_set("log_fields");
+        self
+    }
+
+    /// Sets the handle swapping the generation strategy at runtime, read for each missing request id (default none, i.e. per the configuration).
+    ///
+    /// The generated UUID is formatted, wrapped in the template and transformed like any other, see `GeneratorHandle`.
+    pub fn with_generator_handle(mut self, generator_handle: GeneratorHandle) -> Self {
+        self.generator_handle = Some(generator_handle);
+        self.mark_set("generator_handle");
+        self
+    }
+
+    /// Sets the request ids rejected as `RequestIdError::Denied`, even though they are valid, e.g. known-bad or test ids (default none).
+    ///
+    /// They're matched exactly, after the format validation, so any accepted form of a denied UUID is rejected.
+    /// The status of the response is 403 Forbidden, unless mapped otherwise via `with_status_for`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    /// use uuid::Uuid;
+    ///
+    /// let config = RequestIdConfig::default()
+    ///     .with_denied_ids([Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()]);
+    /// ```
+    pub fn with_denied_ids(mut self, denied_ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.denied_ids = Some(denied_ids.into_iter().collect());
+        self.mark_set("denied_ids");
+        self
+    }
+
+    /// Sets the only request ids accepted from the client, the other valid ones being rejected as `RequestIdError::Denied` (default none, i.e. all).
+    ///
+    /// It's checked like `with_denied_ids`, which takes precedence over it. Generated request ids aren't restricted.
+    pub fn with_allowed_ids(mut self, allowed_ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.allowed_ids = Some(allowed_ids.into_iter().collect());
+        self.mark_set("allowed_ids");
+        self
+    }
+
+    /// Sets a detector of the collisions between generated request ids, e.g. in tests (default none).
+    pub fn with_collision_checker(mut self, collision_checker: CollisionChecker) -> Self {
+        self.collision_checker = Some(collision_checker);
+        self.mark_set("collision_checker");
+        self
+    }
+
+    /// Sets a fixed prefix wrapping the request ids on the wire, e.g. `svc-` for `svc-01965864-f8ab-7eb8-912a-a2c999ab110e` (default none).
+    ///
+    /// It's stripped from the request ids sent by the client, if present, before they're validated, so the extractors return the bare
+    /// UUID, and it's prepended again to the request ids echoed by the layer, generated ones included. Unlike `with_generation_template`,
+    /// the prefix is never part of the request id itself. It's matched per `with_prefix_case_sensitive`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let config = RequestIdConfig::default().with_id_prefix("svc-");
+    /// ```
+    pub fn with_id_prefix(mut self, id_prefix: &str) -> Self {
+        self.id_prefix = Some(id_prefix.to_string());
+        self.mark_set("id_prefix");
+        self
+    }
+
+    /// Sets whether an UUID v7 with all-zero random bits, i.e. `rand_a` and `rand_b`, is rejected as `RequestIdError::ZeroRandom` (default `false`).
+    ///
+    /// Like an all-zero timestamp, see `with_reject_zero_timestamp`, it's valid per version and variant, but strongly hints at a broken generator.
+    pub fn with_reject_zero_random(mut self, reject_zero_random: bool) -> Self {
+        self.reject_zero_random = reject_zero_random;
+        self.mark_set("reject_zero_random");
+        self
+    }
+
+    /// Sets how old, per the configured clock, the timestamp of an UUID v7 sent by the client may be, e.g. against replays (default unlimited).
+    ///
+    /// An older request id is rejected as `RequestIdError::Expired`, since it was issued too long ago to still be valid.
+    pub fn with_max_id_age(mut self, max_id_age: Duration) -> Self {
+        self.max_id_age = Some(max_id_age);
+        self.mark_set("max_id_age");
+        self
+    }
+
+    /// Sets whether the layer wraps the inner service in a `tracing` span carrying the request id (default `true`).
+    #[cfg(feature = "tracing")]
+    pub fn with_trace_span(mut self, trace_span: bool) -> Self {
+        self.trace_span = trace_span;
+        self.mark_set("trace_span");
+        self
+    }
+
+    /// Sets the name of the span field carrying the request id (default `SpanField::RequestId`).
+    #[cfg(feature = "tracing")]
+    pub fn with_span_field(mut self, span_field: SpanField) -> Self {
+        self.span_field = span_field;
+        self.mark_set("span_field");
+        self
+    }
+
+    /// Returns the header used to read and echo the request id.
+    pub fn header_name(&self) -> &HeaderName {
+        &self.header_name
+    }
+
+    /// Returns whether the layer echoes the resolved request id on the response.
+    pub fn echo_response(&self) -> bool {
+        self.echo_response
+    }
+
+    /// Returns whether an UUID v7 with an all-zero timestamp is rejected.
+    pub fn reject_zero_timestamp(&self) -> bool {
+        self.reject_zero_timestamp
+    }
+
+    /// Returns the hook applied to the entropy bytes of each generated UUID v7, if any.
+    pub fn random_bits_hook(&self) -> Option<&RandomBitsHook> {
+        self.random_bits_hook.as_ref().map(|hook| hook.0.as_ref())
+    }
+
+    /// Returns whether the request id is read from the request trailers when the header is missing.
+    pub fn read_trailers(&self) -> bool {
+        self.read_trailers
+    }
+
+    /// Returns whether the layer adds the `X-Request-Id-Handler` response header.
+    pub fn echo_handler_version(&self) -> bool {
+        self.echo_handler_version
+    }
+
+    /// Returns the letter case of the request id.
+    pub fn normalize_case(&self) -> LetterCase {
+        self.normalize_case
+    }
+
+    /// Returns the canonical form the request id is re-formatted into, if any.
+    pub fn canonical_form(&self) -> Option<CanonicalForm> {
+        self.canonical_form
+    }
+
+    /// Returns the condition on the response status for the layer to echo the request id, if any.
+    pub fn echo_on(&self) -> Option<&EchoOn> {
+        self.echo_on.as_ref().map(|echo_on| echo_on.0.as_ref())
+    }
+
+    /// Returns the renderer turning a rejected request id into a response, if any.
+    pub fn rejection_renderer(&self) -> Option<&Arc<dyn RejectionRenderer>> {
+        self.rejection_renderer.as_ref().map(|renderer| &renderer.0)
+    }
+
+    /// Returns the async check that a request id sent by the client has not been seen before, if any.
+    pub fn freshness_check(&self) -> Option<&Arc<FreshnessCheck>> {
+        self.freshness_check
+            .as_ref()
+            .map(|freshness_check| &freshness_check.0)
+    }
+
+    /// Returns the ordered sources the request id is read from, if set, i.e. not the default chain.
+    pub fn sources(&self) -> Option<&[SourceSpec]> {
+        self.sources.as_deref()
+    }
+
+    /// Describes the ordered chain the request id is resolved from, e.g. to check the configuration in the logs at startup.
+    ///
+    /// Each source is described like `header:x-request-id`, and the chain ends with `fetch` when an id fetcher is configured,
+    /// or `generate` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// assert_eq!(
+    ///     RequestIdConfig::default().configured_sources(),
+    ///     vec!["header:x-request-id", "generate"]
+    /// );
+    /// ```
+    pub fn configured_sources(&self) -> Vec<String> {
+        let mut sources = match self.sources() {
+            Some(sources) => sources.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            None => default_sources_of(self)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        };
+
+        sources.push(match self.id_fetcher() {
+            Some(_) => "fetch".to_string(),
+            None => "generate".to_string(),
+        });
+
+        sources
+    }
+
+    /// Resets the sources to the default chain, e.g. to read another header with the same configuration.
+    pub(crate) fn without_sources(mut self) -> Self {
+        self.sources = None;
+        self
+    }
+
+    /// Returns whether generated ids are strictly increasing within the process.
+    pub fn monotonic(&self) -> bool {
+        self.monotonic
+    }
+
+    /// Returns whether the layer accepts invalid request ids instead of rejecting them.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns the async fetcher of new request ids, if any.
+    pub fn id_fetcher(&self) -> Option<&Arc<IdFetcher>> {
+        self.id_fetcher.as_ref().map(|id_fetcher| &id_fetcher.0)
+    }
+
+    /// Returns whether the layer falls back to a locally generated UUID v7 when the id fetcher fails.
+    pub fn id_fetcher_fallback(&self) -> bool {
+        self.id_fetcher_fallback
+    }
+
+    /// Returns the hook called on each rejected request id, if any.
+    pub fn on_rejection(&self) -> Option<&OnRejection> {
+        self.on_rejection
+            .as_ref()
+            .map(|on_rejection| on_rejection.0.as_ref())
+    }
+
+    /// Returns how the request id appears in the crate's own log and trace output.
+    pub fn log_redaction(&self) -> Redaction {
+        self.log_redaction
+    }
+
+    /// Returns whether the layer also echoes the request id on the `Server-Timing` response header.
+    pub fn server_timing(&self) -> bool {
+        self.server_timing
+    }
+
+    /// Returns the provider of the rejection messages, if any.
+    pub fn message_provider(&self) -> Option<&MessageProvider> {
+        self.message_provider
+            .as_ref()
+            .map(|message_provider| message_provider.0.as_ref())
+    }
+
+    /// Returns whether any UUID version is accepted.
+    pub(crate) fn any_version(&self) -> bool {
+        self.any_version
+    }
+
+    /// Returns whether the layer rewrites the request header to the resolved request id.
+    pub fn rewrite_request_header(&self) -> bool {
+        self.rewrite_request_header
+    }
+
+    /// Returns whether the layer injects a `traceparent` request header derived from a generated request id.
+    pub fn synthesize_traceparent(&self) -> bool {
+        self.synthesize_traceparent
+    }
+
+    /// Returns the hook called by the layer on each request id it resolves, if any.
+    pub fn on_resolved(&self) -> Option<&OnResolved> {
+        self.on_resolved
+            .as_ref()
+            .map(|on_resolved| on_resolved.0.as_ref())
+    }
+
+    /// Returns whether the request id is percent-decoded before its validation.
+    pub fn percent_decode(&self) -> bool {
+        self.percent_decode
+    }
+
+    /// Returns the prefixes reserved to internal tooling.
+    pub fn reserved_prefixes(&self) -> &[String] {
+        &self.reserved_prefixes
+    }
+
+    /// Returns the UUID versions accepted from the client.
+    pub fn allowed_versions(&self) -> &[Version] {
+        &self.allowed_versions
+    }
+
+    /// Returns the header carrying the trace-sampling decision, if any.
+    pub fn sampling_header(&self) -> Option<&HeaderName> {
+        self.sampling_header.as_ref()
+    }
+
+    /// Returns the trace-sampling decision when the sampling header is missing or invalid.
+    pub fn sampled_default(&self) -> bool {
+        self.sampled_default
+    }
+
+    /// Returns the pool of pre-generated ids, if any.
+    pub fn id_pool(&self) -> Option<&IdPool> {
+        self.id_pool.as_ref()
+    }
+
+    /// Returns whether the prefix-based checks match case-sensitively.
+    pub fn prefix_case_sensitive(&self) -> bool {
+        self.prefix_case_sensitive
+    }
+
+    /// Returns whether the layer also inserts the `ResolvedRequestId` into the response extensions.
+    pub fn response_extension(&self) -> bool {
+        self.response_extension
+    }
+
+    /// Returns the source of the current time of the time-based validations.
+    pub fn clock(&self) -> &dyn Clock {
+        match &self.clock {
+            Some(clock) => clock.0.as_ref(),
+            None => &SystemClock,
+        }
+    }
+
+    /// Returns how far in the future the timestamp of an UUID v7 sent by the client may be, if limited.
+    pub fn max_clock_skew(&self) -> Option<Duration> {
+        self.max_clock_skew
+    }
+
+    /// Returns whether the request ids sent by the client must be exactly hyphenated.
+    pub fn require_exact_hyphenation(&self) -> bool {
+        self.require_exact_hyphenation
+    }
+
+    /// Returns the predicate deciding whether the request id is required for a given request, if any.
+    pub fn require_id(&self) -> Option<&RequireId> {
+        self.require_id
+            .as_ref()
+            .map(|require_id| require_id.0.as_ref())
+    }
+
+    /// Returns whether the layer also echoes the request id as a response trailer.
+    pub fn echo_in_trailers(&self) -> bool {
+        self.echo_in_trailers
+    }
+
+    /// Returns how much the request ids sent by the client are trusted.
+    pub fn trust_level(&self) -> TrustLevel {
+        self.trust_level
+    }
+
+    /// Returns the template wrapping the generated request ids, if any.
+    pub fn generation_template(&self) -> Option<&IdTemplate> {
+        self.generation_template.as_ref()
+    }
+
+    /// Returns which request id wins when a previous layer already resolved one, and the configured header carries a different one.
+    pub fn extension_vs_header(&self) -> Precedence {
+        self.extension_vs_header
+    }
+
+    /// Returns how the layer echoes the request id when the response already carries the header.
+    pub fn response_write_mode(&self) -> WriteMode {
+        self.response_write_mode
+    }
+
+    /// Returns whether the request ids sent by the client are only trusted over HTTPS.
+    pub fn require_tls(&self) -> bool {
+        self.require_tls
+    }
+
+    /// Returns the hook called with the clock skew of each UUID v7 sent by the client, if any.
+    pub fn on_clock_skew(&self) -> Option<&OnClockSkew> {
+        self.on_clock_skew
+            .as_ref()
+            .map(|on_clock_skew| on_clock_skew.0.as_ref())
+    }
+
+    /// Returns the shadow response header the layer sets to a fresh UUID v7, if any.
+    pub fn shadow_header(&self) -> Option<&HeaderName> {
+        self.shadow_header.as_ref()
+    }
+
+    /// Returns the transform applied to each generated request id, if any.
+    pub fn generate_transform(&self) -> Option<&GenerateTransform> {
+        self.generate_transform
+            .as_ref()
+            .map(|generate_transform| generate_transform.0.as_ref())
+    }
+
+    /// Returns the proxies the request ids sent by the client are trusted from.
+    pub fn trusted_proxies(&self) -> &[IpAddr] {
+        &self.trusted_proxies
+    }
+
+    /// Returns the hook called with the resolution event of each request resolved by the layer, if any.
+    pub fn on_resolution(&self) -> Option<&OnResolution> {
+        self.on_resolution
+            .as_ref()
+            .map(|on_resolution| on_resolution.0.as_ref())
+    }
+
+    /// Returns the request id used when no source carries one and it's required, if any.
+    pub fn fallback_id(&self) -> Option<&str> {
+        self.fallback_id.as_deref()
+    }
+
+    /// Returns the name of the multipart form field read by `ExtractMultipartRequestId`.
+    pub fn multipart_field(&self) -> &str {
+        &self.multipart_field
+    }
+
+    /// Returns whether invalid request ids are rejected, or only logged.
+    pub fn enforcement(&self) -> Enforcement {
+        self.enforcement
+    }
+
+    /// Returns the header negotiating the format of the request id, if any.
+    pub fn version_header(&self) -> Option<&HeaderName> {
+        self.version_header.as_ref()
+    }
+
+    /// Returns whether the layer appends the request id to the `Location` header of the redirect responses.
+    pub fn append_to_location(&self) -> bool {
+        self.append_to_location
+    }
+
+    /// Returns the maximum length of the request ids sent by the client, if limited.
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// Returns the hook mapping each rejection reason to the HTTP status of the response, if any.
+    pub fn status_for(&self) -> Option<&StatusFor> {
+        self.status_for
+            .as_ref()
+            .map(|status_for| status_for.0.as_ref())
+    }
+
+    /// Returns whether a nil UUID sent by the client is handled as a missing request id.
+    pub fn nil_means_generate(&self) -> bool {
+        self.nil_means_generate
+    }
+
+    /// Returns whether the variant of the UUIDs sent by the client is checked together with their version, per RFC 9562.
+    pub fn strict_variant(&self) -> bool {
+        self.strict_variant
+    }
+
+    /// Returns the request fields appended to the log line of each rejected request id.
+    pub fn log_fields(&self) -> &[LogField] {
+        &self.log_fields
+    }
+
+    /// Returns the handle swapping the generation strategy at runtime, if any.
+    pub fn generator_handle(&self) -> Option<&GeneratorHandle> {
+        self.generator_handle.as_ref()
+    }
+
+    /// Returns the request ids rejected even though they are valid, if any.
+    pub fn denied_ids(&self) -> Option<&HashSet<Uuid>> {
+        self.denied_ids.as_ref()
+    }
+
+    /// Returns the only request ids accepted from the client, if restricted.
+    pub fn allowed_ids(&self) -> Option<&HashSet<Uuid>> {
+        self.allowed_ids.as_ref()
+    }
+
+    /// Returns the detector of the collisions between generated request ids, if any.
+    pub fn collision_checker(&self) -> Option<&CollisionChecker> {
+        self.collision_checker.as_ref()
+    }
+
+    /// Returns the fixed prefix stripped from the request ids before validation and re-added when echoed, if any.
+    pub fn id_prefix(&self) -> Option<&str> {
+        self.id_prefix.as_deref()
+    }
+
+    /// Returns whether an UUID v7 with all-zero random bits is rejected.
+    pub fn reject_zero_random(&self) -> bool {
+        self.reject_zero_random
+    }
+
+    /// Returns how old, per its timestamp, an UUID v7 sent by the client may be, if limited.
+    pub fn max_id_age(&self) -> Option<Duration> {
+        self.max_id_age
+    }
+
+    /// Returns whether the layer wraps the inner service in a `tracing` span.
+    #[cfg(feature = "tracing")]
+    pub fn trace_span(&self) -> bool {
+        self.trace_span
+    }
+
+    /// Returns the name of the span field carrying the request id.
+    #[cfg(feature = "tracing")]
+    pub fn span_field(&self) -> SpanField {
+        self.span_field
+    }
+}
+
+impl RequestIdConfig {
+    /// Returns the default configuration, like `Default::default`, in a `const` context.
+    ///
+    /// It lets the configuration be declared as a `static`, without any lazy initialization.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// static CONFIG: RequestIdConfig = RequestIdConfig::const_default();
+    ///
+    /// assert_eq!(CONFIG.header_name(), "x-request-id");
+    /// ```
+    pub const fn const_default() -> Self {
+        Self {
+            header_name: HeaderName::from_static("x-request-id"),
+            echo_response: true,
+            reject_zero_timestamp: false,
+            random_bits_hook: None,
+            read_trailers: false,
+            echo_handler_version: false,
+            normalize_case: LetterCase::Lower,
+            canonical_form: None,
+            echo_on: None,
+            rejection_renderer: None,
+            freshness_check: None,
+            sources: None,
+            monotonic: false,
+            dry_run: false,
+            id_fetcher: None,
+            id_fetcher_fallback: true,
+            on_rejection: None,
+            log_redaction: Redaction::None,
+            server_timing: false,
+            message_provider: None,
+            any_version: false,
+            rewrite_request_header: false,
+            synthesize_traceparent: false,
+            on_resolved: None,
+            percent_decode: false,
+            reserved_prefixes: Vec::new(),
+            allowed_versions: Cow::Borrowed(&[Version::SortRand]),
+            sampling_header: None,
+            sampled_default: false,
+            id_pool: None,
+            prefix_case_sensitive: true,
+            response_extension: false,
+            clock: None,
+            max_clock_skew: None,
+            require_exact_hyphenation: false,
+            require_id: None,
+            echo_in_trailers: false,
+            trust_level: TrustLevel::Edge,
+            generation_template: None,
+            extension_vs_header: Precedence::ExtensionWins,
+            response_write_mode: WriteMode::Overwrite,
+            require_tls: false,
+            on_clock_skew: None,
+            shadow_header: None,
+            generate_transform: None,
+            trusted_proxies: Vec::new(),
+            on_resolution: None,
+            fallback_id: None,
+            multipart_field: Cow::Borrowed("request_id"),
+            enforcement: Enforcement::Enforce,
+            version_header: None,
+            append_to_location: false,
+            max_len: None,
+            status_for: None,
+            nil_means_generate: false,
+            strict_variant: false,
+            log_fields: Vec::new(),
+            generator_handle: None,
+            denied_ids: None,
+            allowed_ids: None,
+            collision_checker: None,
+            id_prefix: None,
+            reject_zero_random: false,
+            max_id_age: None,
+            #[cfg(feature = "tracing")]
+            trace_span: true,
+            #[cfg(feature = "tracing")]
+            span_field: SpanField::RequestId,
+            set_fields: Vec::new(),
+        }
+    }
+
+    /// Layers the fields set on `overrides` via its builder methods onto `base`, e.g. per-route overrides onto the application defaults.
+    ///
+    /// A field set on `overrides` wins whatever its value, so an override can reset a field of `base` back to its default value,
+    /// e.g. `with_echo_response(true)` over a base set to `false`. The other fields are the ones of `base`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestIdConfig;
+    ///
+    /// let base = RequestIdConfig::default().with_header_name("x-correlation-id".parse().unwrap());
+    /// let overrides = RequestIdConfig::default().with_dry_run(true);
+    ///
+    /// let config = RequestIdConfig::merge(base, overrides);
+    ///
+    /// assert_eq!(config.header_name(), "x-correlation-id");
+    /// assert!(config.dry_run());
+    /// ```
+    pub fn merge(base: Self, overrides: Self) -> Self {
+        let is_set = |field| overrides.set_fields.contains(&field);
+
+        let mut set_fields = base.set_fields.clone();
+        set_fields.extend(
+            overrides
+                .set_fields
+                .iter()
+                .filter(|field| !base.set_fields.contains(field)),
+        );
+
+        macro_rules! value {
+            ($field:ident) => {
+                if is_set(stringify!($field)) {
+                    overrides.$field
+                } else {
+                    base.$field
+                }
+            };
+        }
+
+        macro_rules! option {
+            ($field:ident) => {
+                overrides.$field.or(base.$field)
+            };
+        }
+
+        Self {
+            header_name: value!(header_name),
+            echo_response: value!(echo_response),
+            reject_zero_timestamp: value!(reject_zero_timestamp),
+            random_bits_hook: option!(random_bits_hook),
+            read_trailers: value!(read_trailers),
+            echo_handler_version: value!(echo_handler_version),
+            normalize_case: value!(normalize_case),
+            canonical_form: option!(canonical_form),
+            echo_on: option!(echo_on),
+            rejection_renderer: option!(rejection_renderer),
+            freshness_check: option!(freshness_check),
+            sources: option!(sources),
+            monotonic: value!(monotonic),
+            dry_run: value!(dry_run),
+            id_fetcher: option!(id_fetcher),
+            id_fetcher_fallback: value!(id_fetcher_fallback),
+            on_rejection: option!(on_rejection),
+            log_redaction: value!(log_redaction),
+            server_timing: value!(server_timing),
+            message_provider: option!(message_provider),
+            any_version: value!(any_version),
+            rewrite_request_header: value!(rewrite_request_header),
+            synthesize_traceparent: value!(synthesize_traceparent),
+            on_resolved: option!(on_resolved),
+            percent_decode: value!(percent_decode),
+            reserved_prefixes: value!(reserved_prefixes),
+            allowed_versions: value!(allowed_versions),
+            sampling_header: option!(sampling_header),
+            sampled_default: value!(sampled_default),
+            id_pool: option!(id_pool),
+            prefix_case_sensitive: value!(prefix_case_sensitive),
+            response_extension: value!(response_extension),
+            clock: option!(clock),
+            max_clock_skew: option!(max_clock_skew),
+            require_exact_hyphenation: value!(require_exact_hyphenation),
+            require_id: option!(require_id),
+            echo_in_trailers: value!(echo_in_trailers),
+            trust_level: value!(trust_level),
+            generation_template: option!(generation_template),
+            extension_vs_header: value!(extension_vs_header),
+            response_write_mode: value!(response_write_mode),
+            require_tls: value!(require_tls),
+            on_clock_skew: option!(on_clock_skew),
+            shadow_header: option!(shadow_header),
+            generate_transform: option!(generate_transform),
+            trusted_proxies: value!(trusted_proxies),
+            on_resolution: option!(on_resolution),
+            fallback_id: option!(fallback_id),
+            multipart_field: value!(multipart_field),
+            enforcement: value!(enforcement),
+            version_header: option!(version_header),
+            append_to_location: value!(append_to_location),
+            max_len: option!(max_len),
+            status_for: option!(status_for),
+            nil_means_generate: value!(nil_means_generate),
+            strict_variant: value!(strict_variant),
+            log_fields: value!(log_fields),
+            generator_handle: option!(generator_handle),
+            denied_ids: option!(denied_ids),
+            allowed_ids: option!(allowed_ids),
+            collision_checker: option!(collision_checker),
+            id_prefix: option!(id_prefix),
+            reject_zero_random: value!(reject_zero_random),
+            max_id_age: option!(max_id_age),
+            #[cfg(feature = "tracing")]
+            trace_span: value!(trace_span),
+            #[cfg(feature = "tracing")]
+            span_field: value!(span_field),
+            set_fields,
+        }
+    }
+
+    /// Records that the field was set via a builder method, see `merge`.
+    fn mark_set(&mut self, field: &'static str) {
+        if !self.set_fields.contains(&field) {
+            self.set_fields.push(field);
+        }
+    }
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self::const_default()
+    }
+}
+
+static DEFAULT_CONFIG: OnceLock<RequestIdConfig> = OnceLock::new();
+
+/// Sets the process-global configuration, read by `ExtractRequestId` when no layer resolved the request id.
+///
+/// It must be called once, at startup, before serving requests.
+/// If the global configuration is already set, it returns the given configuration back as an error.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestIdConfig, set_default_config};
+///
+/// set_default_config(RequestIdConfig::default().with_reject_zero_timestamp(true)).unwrap();
+///
+/// assert!(set_default_config(RequestIdConfig::default()).is_err());
+/// ```
+// Like `OnceLock::set`, the rejected configuration is handed back as is.
+#[allow(clippy::result_large_err)]
+pub fn set_default_config(config: RequestIdConfig) -> Result<(), RequestIdConfig> {
+    DEFAULT_CONFIG.set(config)
+}
+
+/// Runs the given function with the process-global configuration, or the default one if it has not been set.
+pub(crate) fn with_default_config<T>(f: impl FnOnce(&RequestIdConfig) -> T) -> T {
+    match DEFAULT_CONFIG.get() {
+        Some(config) => f(config),
+        None => f(&RequestIdConfig::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IdSource, RequestIdConfig};
+    use std::time::Duration;
+
+    use axum::http::HeaderName;
+    use uuid::Version;
+
+    #[test]
+    fn test_config_default() {
+        let config = RequestIdConfig::default();
+
+        assert_eq!(config.header_name(), "x-request-id");
+        assert!(config.echo_response());
+        assert!(!config.reject_zero_timestamp());
+    }
+
+    #[test]
+    fn test_config_with_header_name() {
+        let config =
+            RequestIdConfig::new().with_header_name(HeaderName::from_static("x-correlation-id"));
+
+        assert_eq!(config.header_name(), "x-correlation-id");
+    }
+
+    #[test]
+    fn test_config_const_default() {
+        static CONFIG: RequestIdConfig = RequestIdConfig::const_default();
+
+        assert_eq!(CONFIG.header_name(), "x-request-id");
+        assert!(CONFIG.echo_response());
+        assert_eq!(CONFIG.allowed_versions(), [Version::SortRand]);
+    }
+
+    #[test]
+    fn test_config_merge() {
+        let base = RequestIdConfig::default()
+            .with_header_name(HeaderName::from_static("x-correlation-id"))
+            .with_reject_zero_timestamp(true)
+            .with_reserved_prefixes(vec!["internal-".to_string()]);
+        let overrides = RequestIdConfig::default()
+            .with_echo_response(false)
+            .with_reserved_prefixes(vec!["admin-".to_string()])
+            .with_max_clock_skew(Duration::from_secs(5));
+
+        let config = RequestIdConfig::merge(base, overrides);
+
+        assert_eq!(config.header_name(), "x-correlation-id");
+        assert!(config.reject_zero_timestamp());
+        assert!(!config.echo_response());
+        assert_eq!(config.reserved_prefixes(), ["admin-".to_string()]);
+        assert_eq!(config.max_clock_skew(), Some(Duration::from_secs(5)));
+        assert!(!config.dry_run());
+    }
+
+    #[test]
+    fn test_config_configured_sources() {
+        let config = RequestIdConfig::default().with_sources(vec![
+            IdSource::Header(HeaderName::from_static("x-correlation-id")),
+            IdSource::Cookie("request_id".to_string()),
+            IdSource::Query("request_id".to_string()),
+            IdSource::Traceparent,
+        ]);
+
+        assert_eq!(
+            config.configured_sources(),
+            vec![
+                "header:x-correlation-id",
+                "cookie:request_id",
+                "query:request_id",
+                "header:traceparent",
+                "generate"
+            ]
+        );
+        assert_eq!(
+            RequestIdConfig::default()
+                .with_read_trailers(true)
+                .with_id_fetcher(|| Box::pin(async {
+                    Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+                }))
+                .configured_sources(),
+            vec!["header:x-request-id", "trailer:x-request-id", "fetch"]
+        );
+    }
+
+    #[test]
+    fn test_config_merge_back_to_default() {
+        let base = RequestIdConfig::default()
+            .with_echo_response(false)
+            .with_dry_run(true);
+        let overrides = RequestIdConfig::default().with_echo_response(true);
+
+        let config = RequestIdConfig::merge(base, overrides);
+
+        assert!(config.echo_response());
+        assert!(config.dry_run());
+
+        // The merged configuration remembers both, for the next merges.
+        let config = RequestIdConfig::merge(RequestIdConfig::default(), config);
+
+        assert!(config.echo_response());
+        assert!(config.dry_run());
+    }
+
+    #[test]
+    fn test_config_merge_defaults_keep_base() {
+        let base = RequestIdConfig::default()
+            .with_header_name(HeaderName::from_static("x-correlation-id"))
+            .with_on_rejection(|_| {});
+
+        let config = RequestIdConfig::merge(base, RequestIdConfig::default());
+
+        assert_eq!(config.header_name(), "x-correlation-id");
+        assert!(config.on_rejection().is_some());
+    }
+}
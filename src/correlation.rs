@@ -0,0 +1,142 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderName, StatusCode, request::Parts},
+};
+
+use crate::{ExtractRequestId, config::with_default_config, resolve::resolve};
+
+const HEADER_X_CORRELATION_ID: &str = "X-Correlation-Id";
+
+/// This is a custom extractor for Axum that extracts both the per-hop request id, via the `X-Request-Id` header,
+/// and the end-to-end correlation id, via the `X-Correlation-Id` header.
+///
+/// Each id is validated as an UUID v7, and generated independently if its header is missing.
+/// If one of them is invalid, it returns a 400 Bad Request error with a message naming the faulty header.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::ExtractIds;
+///
+/// async fn handler(ExtractIds { request_id, correlation_id }: ExtractIds) {
+///     println!("Request Id: {:?}, Correlation Id: {:?}", request_id, correlation_id);
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtractIds {
+    pub request_id: String,
+    pub correlation_id: String,
+}
+
+impl<S> FromRequestParts<S> for ExtractIds
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractRequestId(request_id) = ExtractRequestId::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| (rejection.status(), rejection.error().to_string()))?;
+
+        let (correlation_id, _) = with_default_config(|config| {
+            let config = config
+                .clone()
+                .with_header_name(HeaderName::from_static("x-correlation-id"))
+                .without_sources();
+
+            resolve(parts, &config)
+        })
+        .map_err(|err| {
+            (
+                err.status(),
+                format!("Invalid {} : {}", HEADER_X_CORRELATION_ID, err.reason()),
+            )
+        })?;
+
+        Ok(ExtractIds {
+            request_id,
+            correlation_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ExtractIds;
+    use axum::{
+        body::Body,
+        extract::FromRequestParts,
+        http::{Request, StatusCode},
+    };
+
+    async fn extract(headers: &[(&str, &str)]) -> Result<ExtractIds, (StatusCode, String)> {
+        let mut request = Request::builder();
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let mut parts = request.body(Body::empty()).unwrap().into_parts();
+
+        ExtractIds::from_request_parts(&mut parts.0, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_correlation_extract_ids_with_both_headers() {
+        let ids = extract(&[
+            ("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e"),
+            ("X-Correlation-Id", "0196583c-4d2a-7087-9beb-6214d18ec924"),
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(ids.request_id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+        assert_eq!(ids.correlation_id, "0196583c-4d2a-7087-9beb-6214d18ec924");
+    }
+
+    #[tokio::test]
+    async fn test_correlation_extract_ids_with_request_id_only() {
+        let ids = extract(&[("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")])
+            .await
+            .unwrap();
+
+        assert_eq!(ids.request_id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+        assert_ne!(ids.correlation_id, ids.request_id);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_extract_ids_with_correlation_id_only() {
+        let ids = extract(&[("X-Correlation-Id", "0196583c-4d2a-7087-9beb-6214d18ec924")])
+            .await
+            .unwrap();
+
+        assert_eq!(ids.correlation_id, "0196583c-4d2a-7087-9beb-6214d18ec924");
+        assert_ne!(ids.request_id, ids.correlation_id);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_extract_ids_without_headers() {
+        let ids = extract(&[]).await.unwrap();
+
+        assert_ne!(ids.request_id, ids.correlation_id);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_extract_ids_with_invalid_correlation_id() {
+        let err = extract(&[("X-Correlation-Id", "this-is-not-a-uuid")])
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid X-Correlation-Id : Not a valid UUID".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::HEADER_X_REQUEST_ID;
+
+/// The reasons why a request id is rejected.
+///
+/// Each variant is rendered as a 400 Bad Request (409 Conflict for `AlreadySeen` and `InFlight`, 503 Service Unavailable for `FetchFailed`, 403 Forbidden for `ReservedPrefix` and `Denied`) with a specific message, e.g. `Invalid X-Request-Id : Not a valid UUID`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestIdError {
+    /// The header value contains non-ASCII characters, i.e. it's an encoding error.
+    NotAscii,
+    /// The header value contains ASCII control characters, e.g. a tab or DEL.
+    ControlCharacter,
+    /// The header value is not a valid UUID, i.e. it's a parsing error.
+    NotUuid,
+    /// The header value is a valid UUID, but not an UUID v7, i.e. it's a version error.
+    NotV7,
+    /// The header value is an UUID v7 with an all-zero timestamp.
+    ZeroTimestamp,
+    /// The header value is an UUID v7 with a timestamp too far in the future, per the configured maximum clock skew.
+    ClockSkew,
+    /// The request id is missing, while it's required for this request, per the configured predicate.
+    Missing,
+    /// The header value differs from the request id already resolved by a previous layer, per the configured precedence.
+    Mismatch,
+    /// The request id has already been seen, per the configured freshness check.
+    AlreadySeen,
+    /// The configured id fetcher failed to fetch a new request id, without fallback to the local generation.
+    FetchFailed,
+    /// The header value starts with a prefix reserved to internal tooling.
+    ReservedPrefix,
+    /// The format negotiated via the configured version header is unknown.
+    UnknownVersion,
+    /// The header value is not a valid ULID, while it's the negotiated format.
+    NotUlid,
+    /// The header value is longer than the configured maximum length.
+    TooLong,
+    /// The header value is an UUID whose variant isn't the RFC 9562 one, while its version requires it.
+    InvalidVariant,
+    /// The header value is a valid request id, but denied, per the configured denylist or allowlist.
+    Denied,
+    /// The header value is an UUID v7 with all-zero random bits.
+    ZeroRandom,
+    /// The header value is an UUID v7 with a timestamp too far in the past, per the configured maximum age.
+    Expired,
+    /// The request id is already borne by as many in-flight requests as allowed, per the `InFlightLayer`.
+    InFlight,
+}
+
+impl RequestIdError {
+    /// Returns the HTTP status code sent back to the client.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RequestIdError::AlreadySeen | RequestIdError::InFlight => StatusCode::CONFLICT,
+            RequestIdError::FetchFailed => StatusCode::SERVICE_UNAVAILABLE,
+            RequestIdError::ReservedPrefix | RequestIdError::Denied => StatusCode::FORBIDDEN,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Returns a short, stable code for the reason, e.g. `not-a-uuid`, suitable for headers and metrics labels.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RequestIdError::NotAscii => "not-ascii",
+            RequestIdError::ControlCharacter => "control-character",
+            RequestIdError::NotUuid => "not-a-uuid",
+            RequestIdError::NotV7 => "not-a-v7",
+            RequestIdError::ZeroTimestamp => "zero-timestamp",
+            RequestIdError::ClockSkew => "clock-skew",
+            RequestIdError::Missing => "missing",
+            RequestIdError::Mismatch => "mismatch",
+            RequestIdError::AlreadySeen => "already-seen",
+            RequestIdError::FetchFailed => "fetch-failed",
+            RequestIdError::ReservedPrefix => "reserved-prefix",
+            RequestIdError::UnknownVersion => "unknown-version",
+            RequestIdError::NotUlid => "not-a-ulid",
+            RequestIdError::TooLong => "too-long",
+            RequestIdError::InvalidVariant => "invalid-variant",
+            RequestIdError::Denied => "denied",
+            RequestIdError::ZeroRandom => "zero-random",
+            RequestIdError::Expired => "expired",
+            RequestIdError::InFlight => "in-flight",
+        }
+    }
+
+    /// Returns the reason, without the header name, e.g. `Not a valid UUID`.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            RequestIdError::NotAscii => "contains non-ASCII characters",
+            RequestIdError::ControlCharacter => "contains control characters",
+            RequestIdError::NotUuid => "Not a valid UUID",
+            RequestIdError::NotV7 => "Not an UUID v7",
+            RequestIdError::ZeroTimestamp => "Zero timestamp",
+            RequestIdError::ClockSkew => "Timestamp in the future",
+            RequestIdError::Missing => "Missing",
+            RequestIdError::Mismatch => "Mismatch with the resolved request id",
+            RequestIdError::AlreadySeen => "Already seen",
+            RequestIdError::FetchFailed => "Failed to fetch a new id",
+            RequestIdError::ReservedPrefix => "Reserved prefix",
+            RequestIdError::UnknownVersion => "Unknown negotiated version",
+            RequestIdError::NotUlid => "Not a valid ULID",
+            RequestIdError::TooLong => "Too long",
+            RequestIdError::InvalidVariant => "Invalid UUID variant",
+            RequestIdError::Denied => "Denied",
+            RequestIdError::ZeroRandom => "Zero random bits",
+            RequestIdError::Expired => "Expired",
+            RequestIdError::InFlight => "Already in flight",
+        }
+    }
+}
+
+impl fmt::Display for RequestIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid {} : {}", HEADER_X_REQUEST_ID, self.reason())
+    }
+}
+
+impl std::error::Error for RequestIdError {}
+
+impl From<RequestIdError> for (StatusCode, String) {
+    fn from(error: RequestIdError) -> Self {
+        (error.status(), error.to_string())
+    }
+}
+
+impl IntoResponse for RequestIdError {
+    fn into_response(self) -> Response {
+        <(StatusCode, String)>::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RequestIdError;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_error_messages_are_distinct() {
+        assert_eq!(
+            RequestIdError::NotAscii.to_string(),
+            "Invalid X-Request-Id : contains non-ASCII characters"
+        );
+        assert_eq!(
+            RequestIdError::NotUuid.to_string(),
+            "Invalid X-Request-Id : Not a valid UUID"
+        );
+    }
+
+    #[test]
+    fn test_error_into_tuple() {
+        assert_eq!(
+            <(StatusCode, String)>::from(RequestIdError::NotV7),
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid X-Request-Id : Not an UUID v7".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(RequestIdError::NotUuid.code(), "not-a-uuid");
+        assert_eq!(RequestIdError::NotV7.code(), "not-a-v7");
+    }
+
+    #[test]
+    fn test_error_boxes_into_std_error() {
+        let error: Box<dyn std::error::Error + Send + Sync> = Box::new(RequestIdError::NotV7);
+
+        assert_eq!(error.to_string(), "Invalid X-Request-Id : Not an UUID v7");
+
+        let parse = || -> Result<(), Box<dyn std::error::Error>> {
+            Err(RequestIdError::NotUuid)?;
+
+            Ok(())
+        };
+
+        assert_eq!(
+            parse().unwrap_err().to_string(),
+            "Invalid X-Request-Id : Not a valid UUID"
+        );
+    }
+}
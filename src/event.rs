@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use axum::http::Extensions;
+
+use crate::{IdSource, RequestIdError};
+
+/// The outcome of the resolution of a request id, see `ResolutionEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResolutionOutcome {
+    /// The request id sent by the client was accepted.
+    Accepted,
+    /// A new request id was generated, or fetched, since none was sent, or since the one sent was invalid in dry-run mode.
+    Generated,
+    /// The request was rejected.
+    Rejected,
+}
+
+/// A structured event describing how the layer resolved the request id of a request, see `RequestIdConfig::with_on_resolution`.
+///
+/// It's emitted once per request resolved by the layer, i.e. not for the requests already resolved by a previous layer.
+/// Unless the request is rejected, it's also inserted into the request extensions, see `resolution_from_extensions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolutionEvent {
+    /// The source carrying the request id sent by the client, if any.
+    pub source: Option<IdSource>,
+    /// The outcome of the resolution.
+    pub outcome: ResolutionOutcome,
+    /// The reason why the request id sent by the client was invalid, if it was.
+    pub reason: Option<RequestIdError>,
+    /// The time spent resolving the request id, including the id fetcher and the freshness check.
+    pub latency: Duration,
+}
+
+/// Returns the event of the resolution of the request id of a request, from its extensions, e.g. in a logging or metrics layer
+/// applied inside the `RequestIdLayer`, to know whether the request id was sent by the client without validating it again.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{extract::Request, middleware::{self, Next}, response::Response, routing::get, Router};
+/// use request_id_middleware::{
+///     RequestIdConfig, RequestIdLayer, ResolutionOutcome, resolution_from_extensions,
+/// };
+///
+/// async fn log_outcome(request: Request, next: Next) -> Response {
+///     if let Some(event) = resolution_from_extensions(request.extensions()) {
+///         println!("Generated : {}", event.outcome == ResolutionOutcome::Generated);
+///     }
+///
+///     next.run(request).await
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(|| async {}))
+///     .layer(middleware::from_fn(log_outcome))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+pub fn resolution_from_extensions(extensions: &Extensions) -> Option<&ResolutionEvent> {
+    extensions.get::<ResolutionEvent>()
+}
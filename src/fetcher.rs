@@ -0,0 +1,314 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use log::warn;
+
+use crate::{BoxError, HEADER_X_REQUEST_ID};
+
+/// An async fetcher of blocks of new request ids, e.g. from a remote sequence service.
+pub type BlockFetcher =
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<Vec<String>, BoxError>> + Send>> + Send + Sync;
+
+/// A spawner of the refills of a `CachedIdFetcher` as background tasks, e.g. via `tokio::spawn`.
+pub type RefillSpawner = dyn Fn(Pin<Box<dyn Future<Output = ()> + Send>>) + Send + Sync;
+
+/// A cache of request ids fetched by blocks, served locally, to keep the per-request latency low with a remote issuer.
+///
+/// It's set via `RequestIdConfig::with_cached_id_fetcher`, and used exactly like an id fetcher, fallback included.
+/// The cache is refilled by the request that finds it empty, or that leaves it below the low watermark after popping its own id.
+/// The latter is served its id right away if a spawner is set via `with_spawner`, the refill running in the background,
+/// or else awaits the refill, since the crate doesn't depend on a runtime.
+/// A single refill below the low watermark runs at a time: the concurrent requests meanwhile are served from the cache without waiting.
+/// Concurrent requests finding the cache empty each fetch a block, and the surplus ids are kept for the next requests.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{CachedIdFetcher, RequestIdConfig};
+/// use uuid::Uuid;
+///
+/// let fetcher = CachedIdFetcher::new(|| {
+///     Box::pin(async { Ok((0..100).map(|_| Uuid::now_v7().to_string()).collect()) })
+/// })
+/// .with_low_watermark(10)
+/// .with_spawner(|refill| {
+///     tokio::spawn(refill);
+/// });
+///
+/// let config = RequestIdConfig::default().with_cached_id_fetcher(fetcher);
+/// ```
+#[derive(Clone)]
+pub struct CachedIdFetcher {
+    fetch_block: Arc<BlockFetcher>,
+    cache: Arc<Mutex<VecDeque<String>>>,
+    refilling: Arc<AtomicBool>,
+    low_watermark: usize,
+    spawner: Option<Arc<RefillSpawner>>,
+}
+
+impl CachedIdFetcher {
+    /// Creates the cache around the given block fetcher, empty until the first request.
+    pub fn new(
+        fetch_block: impl Fn() -> Pin<Box<dyn Future<Output = Result<Vec<String>, BoxError>> + Send>>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        Self {
+            fetch_block: Arc::new(fetch_block),
+            cache: Arc::default(),
+            refilling: Arc::default(),
+            low_watermark: 0,
+            spawner: None,
+        }
+    }
+
+    /// Sets the number of cached ids below which the cache is refilled (default `0`, i.e. once empty).
+    pub fn with_low_watermark(mut self, low_watermark: usize) -> Self {
+        self.low_watermark = low_watermark;
+        self
+    }
+
+    /// Sets the spawner of the refills below the low watermark, so that the request triggering one doesn't await it (default none).
+    pub fn with_spawner(
+        mut self,
+        spawner: impl Fn(Pin<Box<dyn Future<Output = ()> + Send>>) + Send + Sync + 'static,
+    ) -> Self {
+        self.spawner = Some(Arc::new(spawner));
+        self
+    }
+
+    /// Returns the number of cached ids.
+    pub fn len(&self) -> usize {
+        self.cache().len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Serves a cached id, refilling the cache first if it's empty, or afterwards if it's below the low watermark
+    /// and no other request is already refilling it, in the background if a spawner is set.
+    pub async fn fetch(&self) -> Result<String, BoxError> {
+        let popped = self.cache().pop_front();
+
+        match popped {
+            Some(request_id) => {
+                if self.len() < self.low_watermark
+                    && let Some(refilling) = RefillGuard::acquire(&self.refilling)
+                {
+                    let fetcher = self.clone();
+
+                    let refill = async move {
+                        // The popped id is still served, and the next request retries the refill.
+                        if let Err(err) = fetcher.refill().await {
+                            warn!(
+                                "[{}] Failed to refill the cached ids due to : {:?}",
+                                HEADER_X_REQUEST_ID, err
+                            );
+                        }
+
+                        drop(refilling);
+                    };
+
+                    match &self.spawner {
+                        Some(spawner) => spawner(Box::pin(refill)),
+                        None => refill.await,
+                    }
+                }
+
+                Ok(request_id)
+            }
+            None => {
+                self.refill().await?;
+
+                self.cache()
+                    .pop_front()
+                    .ok_or_else(|| "Fetched an empty block of ids".into())
+            }
+        }
+    }
+
+    async fn refill(&self) -> Result<(), BoxError> {
+        let block = (self.fetch_block)().await?;
+
+        self.cache().extend(block);
+
+        Ok(())
+    }
+
+    fn cache(&self) -> MutexGuard<'_, VecDeque<String>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Marks a refill below the low watermark as in flight, until dropped, i.e. even if the request or the task is cancelled.
+struct RefillGuard(Arc<AtomicBool>);
+
+impl RefillGuard {
+    /// Returns the guard, or `None` if another refill is already in flight.
+    fn acquire(refilling: &Arc<AtomicBool>) -> Option<Self> {
+        refilling
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| Self(refilling.clone()))
+    }
+}
+
+impl Drop for RefillGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl fmt::Debug for CachedIdFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedIdFetcher")
+            .field("cached", &self.len())
+            .field("low_watermark", &self.low_watermark)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use futures_util::poll;
+    use tokio::sync::Notify;
+
+    use crate::CachedIdFetcher;
+
+    fn fetcher(blocks: Arc<AtomicUsize>) -> CachedIdFetcher {
+        CachedIdFetcher::new(move || {
+            let block = blocks.fetch_add(1, Ordering::SeqCst);
+
+            Box::pin(
+                async move { Ok((0..3).map(|index| format!("{}-{}", block, index)).collect()) },
+            )
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_refills_when_empty() {
+        let blocks = Arc::new(AtomicUsize::new(0));
+        let fetcher = fetcher(blocks.clone());
+
+        let mut request_ids = Vec::new();
+
+        for _ in 0..7 {
+            request_ids.push(fetcher.fetch().await.unwrap());
+        }
+
+        assert_eq!(
+            request_ids,
+            ["0-0", "0-1", "0-2", "1-0", "1-1", "1-2", "2-0"]
+        );
+        assert_eq!(blocks.load(Ordering::SeqCst), 3);
+        assert_eq!(fetcher.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_refills_below_low_watermark() {
+        let blocks = Arc::new(AtomicUsize::new(0));
+        let fetcher = fetcher(blocks.clone()).with_low_watermark(2);
+
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-0");
+        assert_eq!(blocks.load(Ordering::SeqCst), 1);
+
+        // It leaves a single id, i.e. below the low watermark, so the next block is fetched already.
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-1");
+        assert_eq!(blocks.load(Ordering::SeqCst), 2);
+        assert_eq!(fetcher.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_single_refill_in_flight() {
+        let blocks = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(Notify::new());
+
+        let fetcher = {
+            let blocks = blocks.clone();
+            let release = release.clone();
+
+            CachedIdFetcher::new(move || {
+                let block = blocks.fetch_add(1, Ordering::SeqCst);
+                let release = release.clone();
+
+                Box::pin(async move {
+                    // The first block fills the cache, the next ones wait until released.
+                    if block > 0 {
+                        release.notified().await;
+                    }
+
+                    Ok((0..4).map(|index| format!("{}-{}", block, index)).collect())
+                })
+            })
+            .with_low_watermark(4)
+        };
+
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-0");
+
+        let mut refilling = pin!(fetcher.fetch());
+
+        // It pops its id, then waits for the refill it started.
+        assert!(poll!(refilling.as_mut()).is_pending());
+        assert_eq!(blocks.load(Ordering::SeqCst), 2);
+
+        // The concurrent requests are served without waiting, or starting another refill.
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-2");
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-3");
+        assert_eq!(blocks.load(Ordering::SeqCst), 2);
+
+        release.notify_one();
+
+        assert_eq!(refilling.await.unwrap(), "0-1");
+        assert_eq!(fetcher.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_spawned_refill() {
+        let blocks = Arc::new(AtomicUsize::new(0));
+        let spawned = Arc::new(Mutex::new(Vec::new()));
+
+        let fetcher = {
+            let spawned = spawned.clone();
+
+            fetcher(blocks.clone())
+                .with_low_watermark(2)
+                .with_spawner(move |refill| spawned.lock().unwrap().push(refill))
+        };
+
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-0");
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-1");
+
+        // The request is served without awaiting the refill, which is left to the spawner.
+        assert_eq!(blocks.load(Ordering::SeqCst), 1);
+        assert_eq!(spawned.lock().unwrap().len(), 1);
+
+        // Another refill isn't spawned while the first one is pending.
+        assert_eq!(fetcher.fetch().await.unwrap(), "0-2");
+        assert_eq!(spawned.lock().unwrap().len(), 1);
+
+        let refill = spawned.lock().unwrap().pop().unwrap();
+        refill.await;
+
+        assert_eq!(blocks.load(Ordering::SeqCst), 2);
+        assert_eq!(fetcher.len(), 3);
+    }
+}
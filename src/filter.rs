@@ -0,0 +1,172 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use tower::{Layer, Service};
+
+use crate::{
+    RequestIdConfig, RequestIdRejection, ResolvedRequestId,
+    resolve::{mark_generated, resolve},
+};
+
+/// This is a `tower` layer that only validates the request id, and rejects an invalid one before invoking the inner service.
+///
+/// Unlike `RequestIdLayer`, it doesn't echo the request id nor run any of the other steps, so it can be applied early,
+/// e.g. around the router of a gateway, to reject bad request ids before routing.
+/// A valid request id is inserted into the request extensions as a `ResolvedRequestId`, so that the next layers and extractors reuse it.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{RequestIdConfig, RequestIdFilterLayer};
+/// use tower::Layer;
+///
+/// let app = Router::<()>::new().route("/foo", get(|| async {}));
+///
+/// let service = RequestIdFilterLayer::new(RequestIdConfig::default()).layer(app);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestIdFilterLayer {
+    config: Arc<RequestIdConfig>,
+}
+
+impl RequestIdFilterLayer {
+    /// Creates the layer from the given configuration.
+    pub fn new(config: RequestIdConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for RequestIdFilterLayer {
+    type Service = RequestIdFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdFilterService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// The service produced by `RequestIdFilterLayer`.
+#[derive(Debug, Clone)]
+pub struct RequestIdFilterService<S> {
+    inner: S,
+    config: Arc<RequestIdConfig>,
+}
+
+impl<S> Service<Request> for RequestIdFilterService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let (mut parts, body) = request.into_parts();
+
+        if parts.extensions.get::<ResolvedRequestId>().is_none() {
+            match resolve(&parts, &self.config) {
+                Ok((request_id, generated)) => {
+                    if generated {
+                        mark_generated(&mut parts.extensions, &request_id);
+                    }
+
+                    parts.extensions.insert(ResolvedRequestId {
+                        id: request_id,
+                        generated,
+                        header_name: self.config.header_name().clone(),
+                    });
+                }
+                Err(err) => {
+                    let rejection =
+                        RequestIdRejection::with_config(err, &self.config, &parts.headers);
+
+                    return Box::pin(async move { Ok(rejection.into_response()) });
+                }
+            }
+        }
+
+        // The ready service must be the one called, so keep it and leave the clone in place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move { inner.call(Request::from_parts(parts, body)).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use crate::{RequestIdConfig, RequestIdFilterLayer, ResolvedRequestId};
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        response::{IntoResponse, Response},
+    };
+    use tower::{Layer, ServiceExt, service_fn};
+
+    async fn filter(request_id: &str, calls: Arc<AtomicUsize>) -> Response {
+        let inner = service_fn(move |request: Request<Body>| {
+            let calls = calls.clone();
+
+            async move {
+                calls.fetch_add(1, Ordering::Relaxed);
+
+                let resolved = request.extensions().get::<ResolvedRequestId>().cloned();
+
+                Ok::<_, Infallible>(resolved.unwrap().id.into_response())
+            }
+        });
+
+        RequestIdFilterLayer::new(RequestIdConfig::default())
+            .layer(inner)
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", request_id)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_filter_rejects_before_inner_service() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let response = filter("6edaba95-4f5b-4547-be3f-85210d3ff8bf", calls.clone()).await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        let response = filter("01965864-f8ab-7eb8-912a-a2c999ab110e", calls.clone()).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,234 @@
+use uuid::Uuid;
+
+/// The letter case of the request id returned by the extractor and echoed by the layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LetterCase {
+    /// Lowercase hexadecimal digits, e.g. `01965864-f8ab-...` (default).
+    #[default]
+    Lower,
+    /// Uppercase hexadecimal digits, e.g. `01965864-F8AB-...`.
+    Upper,
+    /// The case sent by the client.
+    ///
+    /// If the request id is also re-formatted via a `CanonicalForm`, the case can't be kept digit by digit :
+    /// it's uppercase if all the letters sent by the client are uppercase, and lowercase otherwise.
+    /// A generated request id is lowercase.
+    Preserve,
+}
+
+/// The canonical form of the request id returned by the extractor and echoed by the layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalForm {
+    /// 36 characters with hyphens, e.g. `01965864-f8ab-7eb8-912a-a2c999ab110e`.
+    Hyphenated,
+    /// 32 characters without hyphens, e.g. `01965864f8ab7eb8912aa2c999ab110e`.
+    Simple,
+    /// 38 characters with hyphens and braces, e.g. `{01965864-f8ab-7eb8-912a-a2c999ab110e}`, as sent by Microsoft clients.
+    Braces,
+}
+
+/// Formats a request id, given the (trimmed) value sent by the client if any, and its parsed UUID.
+///
+/// Without a canonical form, the value sent by the client is kept as is, only its case is normalized.
+/// A generated request id, i.e. without a value sent by the client, is hyphenated by default.
+pub(crate) fn format(
+    sent: Option<&str>,
+    uuid: &Uuid,
+    canonical_form: Option<CanonicalForm>,
+    normalize_case: LetterCase,
+) -> String {
+    let formatted = match (canonical_form, sent) {
+        (None, Some(sent)) => sent.to_string(),
+        (Some(CanonicalForm::Simple), _) => uuid.simple().to_string(),
+        (Some(CanonicalForm::Braces), _) => uuid.braced().to_string(),
+        (Some(CanonicalForm::Hyphenated), _) | (None, None) => uuid.hyphenated().to_string(),
+    };
+
+    let uppercase = match normalize_case {
+        LetterCase::Lower => false,
+        LetterCase::Upper => true,
+        LetterCase::Preserve if canonical_form.is_none() => return formatted,
+        LetterCase::Preserve => sent.is_some_and(|sent| {
+            sent.bytes().any(|byte| byte.is_ascii_uppercase())
+                && !sent.bytes().any(|byte| byte.is_ascii_lowercase())
+        }),
+    };
+
+    if uppercase {
+        formatted.to_ascii_uppercase()
+    } else {
+        formatted.to_ascii_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanonicalForm, LetterCase, format::format};
+    use uuid::Uuid;
+
+    fn uuid() -> Uuid {
+        Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()
+    }
+
+    #[test]
+    fn test_format_default_keeps_sent_form() {
+        assert_eq!(
+            format(
+                Some("01965864F8AB7EB8912AA2C999AB110E"),
+                &uuid(),
+                None,
+                LetterCase::Lower
+            ),
+            "01965864f8ab7eb8912aa2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_format_uppercase_hyphenated() {
+        assert_eq!(
+            format(
+                Some("01965864f8ab7eb8912aa2c999ab110e"),
+                &uuid(),
+                Some(CanonicalForm::Hyphenated),
+                LetterCase::Upper
+            ),
+            "01965864-F8AB-7EB8-912A-A2C999AB110E"
+        );
+    }
+
+    #[test]
+    fn test_format_preserve_uppercase_hyphenated() {
+        assert_eq!(
+            format(
+                Some("01965864F8AB7EB8912AA2C999AB110E"),
+                &uuid(),
+                Some(CanonicalForm::Hyphenated),
+                LetterCase::Preserve
+            ),
+            "01965864-F8AB-7EB8-912A-A2C999AB110E"
+        );
+        assert_eq!(
+            format(
+                Some("01965864F8AB7EB8912aa2c999ab110e"),
+                &uuid(),
+                Some(CanonicalForm::Hyphenated),
+                LetterCase::Preserve
+            ),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_format_lowercase_simple() {
+        assert_eq!(
+            format(
+                Some("01965864-F8AB-7EB8-912A-A2C999AB110E"),
+                &uuid(),
+                Some(CanonicalForm::Simple),
+                LetterCase::Lower
+            ),
+            "01965864f8ab7eb8912aa2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_format_uppercase_simple() {
+        for sent in [
+            Some("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+            Some("01965864F8AB7EB8912AA2C999AB110E"),
+            None,
+        ] {
+            assert_eq!(
+                format(
+                    sent,
+                    &uuid(),
+                    Some(CanonicalForm::Simple),
+                    LetterCase::Upper
+                ),
+                "01965864F8AB7EB8912AA2C999AB110E"
+            );
+        }
+
+        assert_eq!(
+            format(
+                Some("01965864-F8AB-7EB8-912A-A2C999AB110E"),
+                &uuid(),
+                Some(CanonicalForm::Simple),
+                LetterCase::Preserve
+            ),
+            "01965864F8AB7EB8912AA2C999AB110E"
+        );
+    }
+
+    #[test]
+    fn test_format_form_and_case_combinations() {
+        let sent = Some("{01965864-f8AB-7eb8-912A-a2c999ab110e}");
+
+        for (canonical_form, normalize_case, expected) in [
+            (
+                CanonicalForm::Hyphenated,
+                LetterCase::Lower,
+                "01965864-f8ab-7eb8-912a-a2c999ab110e",
+            ),
+            (
+                CanonicalForm::Hyphenated,
+                LetterCase::Upper,
+                "01965864-F8AB-7EB8-912A-A2C999AB110E",
+            ),
+            (
+                CanonicalForm::Simple,
+                LetterCase::Lower,
+                "01965864f8ab7eb8912aa2c999ab110e",
+            ),
+            (
+                CanonicalForm::Simple,
+                LetterCase::Upper,
+                "01965864F8AB7EB8912AA2C999AB110E",
+            ),
+        ] {
+            let formatted = format(sent, &uuid(), Some(canonical_form), normalize_case);
+
+            assert_eq!(formatted, expected);
+            assert_eq!(Uuid::try_parse(&formatted).unwrap(), uuid());
+            assert_eq!(
+                format(
+                    Some(&formatted),
+                    &uuid(),
+                    Some(canonical_form),
+                    LetterCase::Preserve
+                ),
+                formatted
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_generated_is_hyphenated() {
+        assert_eq!(
+            format(None, &uuid(), None, LetterCase::Preserve),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_format_braces_preserve_case() {
+        assert_eq!(
+            format(
+                Some("{01965864-F8AB-7EB8-912A-A2C999AB110E}"),
+                &uuid(),
+                Some(CanonicalForm::Braces),
+                LetterCase::Preserve
+            ),
+            "{01965864-F8AB-7EB8-912A-A2C999AB110E}"
+        );
+        assert_eq!(
+            format(
+                None,
+                &uuid(),
+                Some(CanonicalForm::Braces),
+                LetterCase::Lower
+            ),
+            "{01965864-f8ab-7eb8-912a-a2c999ab110e}"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::ConnectInfo,
+    http::{header::FORWARDED, request::Parts, uri::Scheme},
+};
+use log::warn;
+
+use crate::RequestIdConfig;
+
+const HEADER_X_FORWARDED_FOR: &str = "x-forwarded-for";
+const HEADER_X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// Returns whether the request ids sent by the client are trusted, per `RequestIdConfig::with_require_tls`
+/// and `RequestIdConfig::with_trusted_proxies`.
+pub(crate) fn is_trusted(parts: &Parts, config: &RequestIdConfig) -> bool {
+    if config.require_tls() && !is_https(parts) {
+        warn!(
+            "[{}] Ignoring the request id sent by the client due to : Not HTTPS",
+            config.header_name()
+        );
+
+        return false;
+    }
+
+    if !config.trusted_proxies().is_empty()
+        && !immediate_upstream(parts).is_some_and(|ip| config.trusted_proxies().contains(&ip))
+    {
+        warn!(
+            "[{}] Ignoring the request id sent by the client due to : Untrusted proxy",
+            config.header_name()
+        );
+
+        return false;
+    }
+
+    true
+}
+
+/// Returns whether the request was sent over HTTPS, per the `X-Forwarded-Proto` and `Forwarded` headers set by the proxy, or the URI.
+fn is_https(parts: &Parts) -> bool {
+    let proto = header(parts, HEADER_X_FORWARDED_PROTO)
+        .and_then(|protos| protos.split(',').next())
+        .or_else(|| {
+            header(parts, FORWARDED.as_str())
+                .and_then(|forwarded| forwarded.split(',').next())
+                .and_then(|element| forwarded_param(element, "proto"))
+        });
+
+    match proto {
+        Some(proto) => proto.trim().eq_ignore_ascii_case("https"),
+        None => parts.uri.scheme() == Some(&Scheme::HTTPS),
+    }
+}
+
+/// Returns the address of the immediate upstream, i.e. the last hop of the `X-Forwarded-For` or `Forwarded` chain,
+/// or the peer of the connection, per the `ConnectInfo<SocketAddr>` extension, when there's no chain.
+pub(crate) fn immediate_upstream(parts: &Parts) -> Option<IpAddr> {
+    let hop = header(parts, HEADER_X_FORWARDED_FOR)
+        .and_then(|chain| chain.rsplit(',').next())
+        .or_else(|| {
+            header(parts, FORWARDED.as_str())
+                .and_then(|forwarded| forwarded.rsplit(',').next())
+                .and_then(|element| forwarded_param(element, "for"))
+        });
+
+    match hop {
+        Some(hop) => parse_node(hop),
+        None => parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip()),
+    }
+}
+
+/// Parses a node of a forwarded chain, i.e. an IPv4 or IPv6 address, with an optional port, e.g. `"[2001:db8::1]:4711"`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+
+    if let Some(bracketed) = node.strip_prefix('[') {
+        return bracketed.split(']').next()?.parse().ok();
+    }
+
+    node.parse()
+        .ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|address| address.ip()))
+}
+
+fn header<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts
+        .headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Returns the value of the given parameter of a `Forwarded` element, e.g. `https` for `proto` in `for=192.0.2.60;proto=https`.
+fn forwarded_param<'a>(element: &'a str, key: &str) -> Option<&'a str> {
+    element.split(';').find_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+
+        name.trim()
+            .eq_ignore_ascii_case(key)
+            .then_some(value.trim().trim_matches('"'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use crate::forwarded::parse_node;
+
+    #[test]
+    fn test_forwarded_parse_node() {
+        assert_eq!(
+            parse_node(" 203.0.113.43"),
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 43)))
+        );
+        assert_eq!(
+            parse_node("203.0.113.43:4711"),
+            Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 43)))
+        );
+        assert_eq!(
+            parse_node("\"[2001:db8::1]:4711\""),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+        );
+        assert_eq!(
+            parse_node("2001:db8::1"),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)))
+        );
+        assert_eq!(parse_node("unknown"), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::Mutex;
+
+use uuid::{Builder, ContextV7, Timestamp, Uuid};
+
+use crate::{GeneratorHandle, IdPool, RequestId, RequestIdConfig, format::format};
+
+/// The counter shared by all monotonic ids of the process.
+pub(crate) static MONOTONIC_CONTEXT: Mutex<ContextV7> = Mutex::new(ContextV7::new());
+
+/// Generates a new UUID v7, formatted per the configured canonical form and letter case, wrapped in the configured template if any,
+/// then transformed by the configured transform if any.
+pub(crate) fn generate(config: &RequestIdConfig) -> String {
+    let uuid = generate_uuid(config);
+
+    if let Some(collision_checker) = config.collision_checker() {
+        collision_checker.check(&uuid, config.log_redaction());
+    }
+    let request_id = format(
+        None,
+        &uuid,
+        config.canonical_form(),
+        config.normalize_case(),
+    );
+
+    let request_id = match config.generation_template() {
+        Some(template) => template.expand(&request_id, unix_ms(&uuid)),
+        None => request_id,
+    };
+
+    match config.generate_transform() {
+        Some(generate_transform) => generate_transform(request_id),
+        None => request_id,
+    }
+}
+
+/// Returns the timestamp of an UUID v7, in milliseconds since the Unix epoch.
+fn unix_ms(uuid: &Uuid) -> u64 {
+    let (seconds, nanos) = uuid
+        .get_timestamp()
+        .map_or((0, 0), |timestamp| timestamp.to_unix());
+
+    seconds * 1_000 + u64::from(nanos / 1_000_000)
+}
+
+/// Generates a batch of `n` UUIDs v7, strictly increasing, e.g. to populate fixtures where ordering matters.
+///
+/// They share the monotonic counter of `RequestIdConfig::with_monotonic`, so they're also ordered after the
+/// monotonic ids already generated by the process.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::generate_batch;
+///
+/// let request_ids = generate_batch(3);
+///
+/// assert!(request_ids[0] < request_ids[1] && request_ids[1] < request_ids[2]);
+/// ```
+pub fn generate_batch(n: usize) -> Vec<RequestId> {
+    (0..n)
+        .map(|_| RequestId::from(Uuid::new_v7(Timestamp::now(&MONOTONIC_CONTEXT))))
+        .collect()
+}
+
+/// Generates a new UUID v7, per the strategy of the configured generator handle if any, popped from the configured pool if any,
+/// or applying the configured random bits hook if any.
+fn generate_uuid(config: &RequestIdConfig) -> Uuid {
+    if let Some(request_id) = config
+        .generator_handle()
+        .and_then(GeneratorHandle::generate)
+    {
+        return request_id;
+    }
+
+    if let Some(request_id) = config.id_pool().and_then(IdPool::pop) {
+        return request_id;
+    }
+
+    if config.monotonic() {
+        return Uuid::new_v7(Timestamp::now(&MONOTONIC_CONTEXT));
+    }
+
+    let request_id = Uuid::now_v7();
+
+    let Some(random_bits_hook) = config.random_bits_hook() else {
+        return request_id;
+    };
+
+    let millis = unix_ms(&request_id);
+
+    let mut random_bytes = [0; 10];
+    random_bytes.copy_from_slice(&request_id.as_bytes()[6..]);
+
+    random_bits_hook(&mut random_bytes);
+
+    Builder::from_unix_timestamp_millis(millis, &random_bytes).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{IdTemplate, RequestIdConfig, generate::generate, generate_batch};
+    use uuid::{Uuid, Version};
+
+    #[test]
+    fn test_generate_without_hook() {
+        let request_id = Uuid::try_parse(&generate(&RequestIdConfig::default())).unwrap();
+
+        assert_eq!(request_id.get_version(), Some(Version::SortRand));
+    }
+
+    #[test]
+    fn test_generate_with_random_bits_hook() {
+        let config = RequestIdConfig::default().with_random_bits_hook(|bytes| {
+            bytes[8] = 0xab;
+            bytes[9] = 0xcd;
+        });
+
+        let request_id = generate(&config);
+
+        assert!(request_id.ends_with("abcd"), "{}", request_id);
+        assert_eq!(
+            Uuid::try_parse(&request_id).unwrap().get_version(),
+            Some(Version::SortRand)
+        );
+    }
+
+    #[test]
+    fn test_generate_with_template() {
+        let config = RequestIdConfig::default()
+            .with_generation_template(IdTemplate::new("req_{unix_ms}_{uuid}"));
+
+        let request_id = generate(&config);
+        let (unix_ms, uuid) = request_id
+            .strip_prefix("req_")
+            .and_then(|request_id| request_id.split_once('_'))
+            .unwrap();
+        let uuid = Uuid::try_parse(uuid).unwrap();
+        let (seconds, nanos) = uuid.get_timestamp().unwrap().to_unix();
+
+        assert_eq!(uuid.get_version(), Some(Version::SortRand));
+        assert_eq!(
+            unix_ms.parse::<u64>().unwrap(),
+            seconds * 1_000 + u64::from(nanos / 1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_generate_with_transform() {
+        let config = RequestIdConfig::default()
+            .with_generate_transform(|request_id| format!("eu-west-1:{}", request_id));
+
+        let request_id = generate(&config);
+        let uuid = Uuid::try_parse(request_id.strip_prefix("eu-west-1:").unwrap()).unwrap();
+
+        assert_eq!(uuid.get_version(), Some(Version::SortRand));
+    }
+
+    #[test]
+    fn test_generate_monotonic() {
+        let config = RequestIdConfig::default().with_monotonic(true);
+
+        let request_ids = (0..10_000)
+            .map(|_| Uuid::try_parse(&generate(&config)).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(
+            request_ids.windows(2).all(|pair| pair[0] < pair[1]),
+            "Generated ids are not strictly increasing"
+        );
+        assert!(
+            request_ids
+                .iter()
+                .all(|request_id| request_id.get_version() == Some(Version::SortRand))
+        );
+    }
+
+    #[test]
+    fn test_generate_batch_sorted_and_unique() {
+        let request_ids = generate_batch(10_000);
+
+        assert_eq!(request_ids.len(), 10_000);
+        assert!(request_ids.is_sorted());
+        assert_eq!(request_ids.iter().collect::<HashSet<_>>().len(), 10_000);
+        assert!(
+            request_ids
+                .iter()
+                .all(|request_id| request_id.version() == Some(Version::SortRand))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use uuid::{Timestamp, Uuid};
+
+use crate::{IdPool, generate::MONOTONIC_CONTEXT};
+
+/// A strategy generating the missing request ids, see `GeneratorHandle`.
+#[derive(Clone, Default)]
+pub enum GenerationStrategy {
+    /// The generation per the configuration, i.e. `RequestIdConfig::with_id_pool`, `with_monotonic` and `with_random_bits_hook` (default).
+    #[default]
+    Configured,
+    /// A random UUID v7, ignoring the configuration.
+    Random,
+    /// A strictly increasing UUID v7, from the counter shared by the monotonic ids of the process.
+    Monotonic,
+    /// An UUID v7 popped from the given pool, or generated per the configuration when it's empty.
+    Pool(IdPool),
+    /// An UUID returned by the given function, e.g. from an external id service, formatted per the configuration.
+    External(Arc<dyn Fn() -> Uuid + Send + Sync>),
+}
+
+impl fmt::Debug for GenerationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerationStrategy::Configured => f.write_str("Configured"),
+            GenerationStrategy::Random => f.write_str("Random"),
+            GenerationStrategy::Monotonic => f.write_str("Monotonic"),
+            GenerationStrategy::Pool(pool) => f.debug_tuple("Pool").field(pool).finish(),
+            GenerationStrategy::External(_) => f.write_str("External"),
+        }
+    }
+}
+
+/// A handle swapping the generation strategy at runtime, e.g. to A/B test strategies without restarting, see `RequestIdConfig::with_generator_handle`.
+///
+/// It's cheap to clone, and all the clones share the same strategy, so the operators keep one to call `set`
+/// while the configuration reads it for each generated request id.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{GenerationStrategy, GeneratorHandle, RequestIdConfig};
+///
+/// let handle = GeneratorHandle::new(GenerationStrategy::Random);
+///
+/// let config = RequestIdConfig::default().with_generator_handle(handle.clone());
+///
+/// handle.set(GenerationStrategy::Monotonic);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorHandle {
+    strategy: Arc<RwLock<GenerationStrategy>>,
+}
+
+impl GeneratorHandle {
+    /// Creates the handle with the given strategy.
+    pub fn new(strategy: GenerationStrategy) -> Self {
+        Self {
+            strategy: Arc::new(RwLock::new(strategy)),
+        }
+    }
+
+    /// Replaces the strategy, for the request ids generated from now on.
+    pub fn set(&self, strategy: GenerationStrategy) {
+        *self
+            .strategy
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = strategy;
+    }
+
+    /// Generates an UUID per the current strategy, or returns `None` to generate it per the configuration.
+    pub(crate) fn generate(&self) -> Option<Uuid> {
+        match &*self.strategy.read().unwrap_or_else(PoisonError::into_inner) {
+            GenerationStrategy::Configured => None,
+            GenerationStrategy::Random => Some(Uuid::now_v7()),
+            GenerationStrategy::Monotonic => Some(Uuid::new_v7(Timestamp::now(&MONOTONIC_CONTEXT))),
+            GenerationStrategy::Pool(pool) => pool.pop(),
+            GenerationStrategy::External(external) => Some(external()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{GenerationStrategy, GeneratorHandle, RequestIdConfig, validate_or_generate};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_generator_set_strategy() {
+        let handle = GeneratorHandle::new(GenerationStrategy::Random);
+        let config = RequestIdConfig::default()
+            .with_random_bits_hook(|bytes| bytes.fill(0))
+            .with_generator_handle(handle.clone());
+
+        let random_bytes = |config: &RequestIdConfig| {
+            let (request_id, _) = validate_or_generate(None, config).unwrap();
+
+            Uuid::try_parse(&request_id).unwrap().as_bytes()[9..].to_vec()
+        };
+
+        // The random strategy ignores the configured random bits hook.
+        assert_ne!(random_bytes(&config), vec![0; 7]);
+
+        handle.set(GenerationStrategy::External(Arc::new(|| {
+            Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()
+        })));
+
+        assert_eq!(
+            validate_or_generate(None, &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), true))
+        );
+
+        handle.set(GenerationStrategy::Configured);
+
+        assert_eq!(random_bytes(&config), vec![0; 7]);
+    }
+}
@@ -0,0 +1,168 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use uuid::{Uuid, Version};
+
+use crate::{
+    RequestIdRejection, ResolvedRequestId,
+    config::with_default_config,
+    resolve::{debug_assert_single_generation, mark_generated, resolve},
+};
+
+/// This is a custom extractor for Axum that accepts a request id of any UUID version, and flags whether it's an UUID v7.
+///
+/// It lets a handler log the clients that don't comply with the UUID v7 requirement yet, without rejecting them.
+/// A request id that is not an UUID at all is still rejected, and a missing one is generated as an UUID v7.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::ExtractRequestIdGraded;
+///
+/// async fn handler(ExtractRequestIdGraded { id, strict_v7 }: ExtractRequestIdGraded) {
+///     if !strict_v7 {
+///         println!("Non-compliant Request Id: {:?}", id);
+///     }
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractRequestIdGraded {
+    /// The request id.
+    pub id: String,
+    /// Whether the request id is an UUID v7, i.e. it meets the requirement of `ExtractRequestId`.
+    pub strict_v7: bool,
+}
+
+impl<S> FromRequestParts<S> for ExtractRequestIdGraded
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(resolved) = parts.extensions.get::<ResolvedRequestId>() {
+            debug_assert_single_generation(&parts.extensions, resolved);
+
+            return Ok(ExtractRequestIdGraded {
+                strict_v7: is_v7(&resolved.id),
+                id: resolved.id.clone(),
+            });
+        }
+
+        let (id, generated, header_name) = with_default_config(|config| {
+            let config = config.clone().with_any_version(true);
+
+            resolve(parts, &config)
+                .map(|(id, generated)| (id, generated, config.header_name().clone()))
+                .map_err(|err| RequestIdRejection::with_config(err, &config, &parts.headers))
+        })?;
+
+        // Only a generated request id is shared, since a non-v7 one must not leak to `ExtractRequestId`.
+        if generated {
+            mark_generated(&mut parts.extensions, &id);
+
+            parts.extensions.insert(ResolvedRequestId {
+                id: id.clone(),
+                generated,
+                header_name,
+            });
+        }
+
+        let strict_v7 = is_v7(&id);
+
+        Ok(ExtractRequestIdGraded { id, strict_v7 })
+    }
+}
+
+/// Returns whether the request id is an UUID v7, whichever layer or extractor resolved it.
+fn is_v7(request_id: &str) -> bool {
+    Uuid::try_parse(request_id).is_ok_and(|uuid| uuid.get_version() == Some(Version::SortRand))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtractRequestIdGraded, ResolvedRequestId};
+    use axum::{
+        body::Body,
+        extract::FromRequestParts,
+        http::{HeaderName, Request},
+    };
+
+    async fn extract(request_id: Option<&str>) -> ExtractRequestIdGraded {
+        let mut request = Request::builder();
+
+        if let Some(request_id) = request_id {
+            request = request.header("X-Request-Id", request_id);
+        }
+
+        let mut parts = request.body(Body::empty()).unwrap().into_parts();
+
+        ExtractRequestIdGraded::from_request_parts(&mut parts.0, &())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_graded_v4() {
+        assert_eq!(
+            extract(Some("6edaba95-4f5b-4547-be3f-85210d3ff8bf")).await,
+            ExtractRequestIdGraded {
+                id: "6edaba95-4f5b-4547-be3f-85210d3ff8bf".to_string(),
+                strict_v7: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graded_v7() {
+        assert_eq!(
+            extract(Some("01965864-f8ab-7eb8-912a-a2c999ab110e")).await,
+            ExtractRequestIdGraded {
+                id: "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                strict_v7: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graded_missing() {
+        assert!(extract(None).await.strict_v7);
+    }
+
+    #[tokio::test]
+    async fn test_graded_pre_resolved_v4() {
+        let mut parts = Request::builder().body(Body::empty()).unwrap().into_parts();
+
+        parts.0.extensions.insert(ResolvedRequestId {
+            id: "6edaba95-4f5b-4547-be3f-85210d3ff8bf".to_string(),
+            generated: false,
+            header_name: HeaderName::from_static("x-request-id"),
+        });
+
+        assert_eq!(
+            ExtractRequestIdGraded::from_request_parts(&mut parts.0, &())
+                .await
+                .unwrap(),
+            ExtractRequestIdGraded {
+                id: "6edaba95-4f5b-4547-be3f-85210d3ff8bf".to_string(),
+                strict_v7: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_graded_not_uuid() {
+        let mut parts = Request::builder()
+            .header("X-Request-Id", "this-is-not-a-uuid")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+
+        assert!(
+            ExtractRequestIdGraded::from_request_parts(&mut parts.0, &())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,792 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::http::{StatusCode, request::Parts};
+use uuid::{Builder, Uuid, Variant, Version};
+
+use crate::{RequestIdConfig, RequestIdError, ResolvedRequestId, resolve::resolve};
+
+/// A request id, backed by an UUID.
+///
+/// There are two ways to build it from an `Uuid` :
+///
+/// - `RequestId::from(uuid)` trusts the caller, and wraps the UUID as is, whatever its version ;
+/// - `RequestId::try_from_uuid(uuid)` enforces the same UUID v7 rule as the extractor, and returns an error otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestId, RequestIdError};
+/// use uuid::Uuid;
+///
+/// let uuid = Uuid::now_v7();
+///
+/// assert_eq!(RequestId::try_from_uuid(uuid), Ok(RequestId::from(uuid)));
+/// assert_eq!(RequestId::try_from_uuid(Uuid::nil()), Err(RequestIdError::NotV7));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestId(Uuid);
+
+impl RequestId {
+    /// Wraps the given UUID if it's an UUID v7, or returns `RequestIdError::NotV7` otherwise.
+    pub fn try_from_uuid(uuid: Uuid) -> Result<Self, RequestIdError> {
+        match uuid.get_version() {
+            Some(Version::SortRand) => Ok(Self(uuid)),
+            _ => Err(RequestIdError::NotV7),
+        }
+    }
+
+    /// Builds an UUID v7 from its components, e.g. stored in separate columns : the timestamp, in milliseconds since the Unix epoch,
+    /// and the random bytes.
+    ///
+    /// Only the 48 lower bits of the timestamp are kept, and 6 bits of the random bytes are overwritten by the version and variant,
+    /// i.e. the 4 upper bits of the first byte and the 2 upper bits of the third one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// use request_id_middleware::RequestId;
+    ///
+    /// let request_id = RequestId::from_parts(1_745_239_734_443, [0x0e, 0xb8, 0x91, 0x2a, 0xa2, 0xc9, 0x99, 0xab, 0x11, 0x0e]);
+    ///
+    /// assert_eq!(request_id.to_string(), "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    /// assert_eq!(request_id.system_time(), Some(UNIX_EPOCH + Duration::from_millis(1_745_239_734_443)));
+    /// ```
+    pub fn from_parts(unix_ms: u64, random: [u8; 10]) -> RequestId {
+        Self(Builder::from_unix_timestamp_millis(unix_ms, &random).into_uuid())
+    }
+
+    /// Parses an UUID v7 literal at compile time, for the `request_id!` macro.
+    #[doc(hidden)]
+    pub const fn from_literal(literal: &str) -> Option<Self> {
+        match Uuid::try_parse(literal) {
+            Ok(uuid) if uuid.get_version_num() == 7 => Some(Self(uuid)),
+            _ => None,
+        }
+    }
+
+    /// Resolves the request id exactly like the extractor decides, and returns the status it would reject the request with, along with the reason.
+    ///
+    /// It lets custom handlers translate the decision to their own response types. A request id already resolved by a previous layer,
+    /// i.e. the `ResolvedRequestId` extension, is returned as is. The async freshness check and id fetcher are skipped, and a
+    /// request id that isn't an UUID, e.g. per `RequestIdConfig::with_generation_template`, is rejected as `RequestIdError::NotUuid`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::http::{Request, StatusCode};
+    /// use request_id_middleware::{RequestId, RequestIdConfig, RequestIdError};
+    ///
+    /// let (parts, _) = Request::builder().header("x-request-id", "foo").body(()).unwrap().into_parts();
+    ///
+    /// assert_eq!(
+    ///     RequestId::resolve(&parts, &RequestIdConfig::default()),
+    ///     Err((StatusCode::BAD_REQUEST, RequestIdError::NotUuid))
+    /// );
+    /// ```
+    pub fn resolve(
+        parts: &Parts,
+        config: &RequestIdConfig,
+    ) -> Result<Self, (StatusCode, RequestIdError)> {
+        let request_id = match parts.extensions.get::<ResolvedRequestId>() {
+            Some(resolved) => resolved.id.clone(),
+            None => resolve(parts, config).map_err(|err| (err.status(), err))?.0,
+        };
+
+        Uuid::try_parse(&request_id)
+            .map(Self)
+            .map_err(|_| (StatusCode::BAD_REQUEST, RequestIdError::NotUuid))
+    }
+
+    /// Returns the underlying UUID.
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+
+    /// Returns the 16 bytes of the request id, e.g. to store it as a binary database key.
+    pub fn as_bytes(&self) -> [u8; 16] {
+        *self.0.as_bytes()
+    }
+
+    /// Returns the generation time embedded in an UUID v7, with a millisecond precision, or `None` for other versions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+    ///
+    /// assert_eq!(request_id.system_time(), Some(UNIX_EPOCH + Duration::from_millis(1_745_239_734_443)));
+    /// ```
+    pub fn system_time(&self) -> Option<SystemTime> {
+        if self.version() != Some(Version::SortRand) {
+            return None;
+        }
+
+        let (seconds, nanos) = self.0.get_timestamp()?.to_unix();
+
+        Some(UNIX_EPOCH + Duration::new(seconds, nanos))
+    }
+
+    /// Returns whether both request ids were generated within the given window of each other, per their embedded timestamps,
+    /// e.g. to group the requests of a burst. It returns `false` if either of them is not an UUID v7.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::now_v7());
+    ///
+    /// assert!(request_id.within(&request_id.derive_child(), Duration::ZERO));
+    /// ```
+    pub fn within(&self, other: &RequestId, window: Duration) -> bool {
+        match (self.system_time(), other.system_time()) {
+            (Some(time), Some(other)) => time
+                .duration_since(other)
+                .or_else(|_| other.duration_since(time))
+                .is_ok_and(|elapsed| elapsed <= window),
+            _ => false,
+        }
+    }
+
+    /// Returns the UUID version sent by the client, e.g. `Some(Version::SortRand)` for an UUID v7.
+    pub fn version(&self) -> Option<Version> {
+        self.0.get_version()
+    }
+
+    /// Returns the UUID variant sent by the client, e.g. `Variant::RFC4122` for an UUID v7.
+    pub fn variant(&self) -> Variant {
+        self.0.get_variant()
+    }
+
+    /// Returns the 122 hash bits of a name-based UUID v3 or v5, i.e. without its version and variant bits, or `None` for other versions.
+    ///
+    /// A name-based UUID is a hash of a namespace and a name, so the namespace can't be read back from it :
+    /// verifying it requires the name, by hashing both again and comparing these bits.
+    pub fn namespace_bits(&self) -> Option<u128> {
+        match self.version() {
+            Some(Version::Md5 | Version::Sha1) => {
+                Some(self.0.as_u128() & !0x0000_0000_0000_f000_c000_0000_0000_0000)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the given value denotes the same request id, whatever its form or case.
+    ///
+    /// The value is parsed as an UUID, e.g. `{UUID}`, `urn:uuid:UUID`, hyphen-less and uppercase forms,
+    /// and its 128-bit value is compared. If it's not an UUID, it returns `false`.
+    pub fn same_id(&self, other: &str) -> bool {
+        Uuid::try_parse(other.trim()).is_ok_and(|other| self.0 == other)
+    }
+
+    /// Generates a child request id, i.e. a new UUID v7 sharing the timestamp of this one, with fresh random bits.
+    ///
+    /// It lets fan-out operations group their requests chronologically with their parent's.
+    /// For versions other than v7, the first 48 bits are copied as is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let parent = RequestId::from(Uuid::now_v7());
+    /// let child = parent.derive_child();
+    ///
+    /// assert_eq!(child.system_time(), parent.system_time());
+    /// assert_ne!(child, parent);
+    /// ```
+    pub fn derive_child(&self) -> RequestId {
+        let mut bytes = *Uuid::now_v7().as_bytes();
+        bytes[..6].copy_from_slice(&self.0.as_bytes()[..6]);
+
+        Self(Uuid::from_bytes(bytes))
+    }
+
+    /// Formats the request id for the `logging.googleapis.com/trace` field of Google Cloud Logging,
+    /// i.e. `projects/PROJECT_ID/traces/TRACE_ID` where the trace id is the 32 lowercase hexadecimal characters of the UUID.
+    pub fn to_gcp_trace(&self, project_id: &str) -> String {
+        format!("projects/{}/traces/{}", project_id, self.0.simple())
+    }
+
+    /// Returns the 32 lowercase hexadecimal characters of the UUID, without hyphens, e.g. as a fixed-width primary key.
+    ///
+    /// The keys sort like the UUIDs, so they only sort chronologically for UUIDs v7, whose upper bits are the timestamp.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+    ///
+    /// assert_eq!(request_id.to_sortable_key(), "01965864f8ab7eb8912aa2c999ab110e");
+    /// ```
+    pub fn to_sortable_key(&self) -> String {
+        let mut buffer = Uuid::encode_buffer();
+
+        self.0.simple().encode_lower(&mut buffer).to_string()
+    }
+
+    /// Returns whether the request is sampled at the given rate, between 0 and 1, decided from the 64 lower bits of the UUID.
+    ///
+    /// The decision is deterministic, so every service sampling the same request id at the same rate agrees on it,
+    /// and a request sampled at a given rate is also sampled at any higher one. These bits are mixed first, since
+    /// the UUIDs v7 generated within the same millisecond may share their upper random bits, e.g. as a monotonic counter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::now_v7());
+    ///
+    /// assert_eq!(request_id.sample(0.1), request_id.sample(0.1));
+    /// assert!(request_id.sample(1.0));
+    /// assert!(!request_id.sample(0.0));
+    /// ```
+    pub fn sample(&self, rate: f64) -> bool {
+        // The finalizer of SplitMix64, spreading any change of the input over all the bits.
+        let mut bits = self.0.as_u64_pair().1;
+        bits = (bits ^ (bits >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        bits = (bits ^ (bits >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        bits ^= bits >> 31;
+
+        rate >= 1.0 || (bits as f64) < rate * u64::MAX as f64
+    }
+}
+
+#[cfg(feature = "datadog")]
+impl RequestId {
+    /// Returns the 64-bit trace id of Datadog's log-trace correlation, i.e. the `dd.trace_id` field, from the 64 lower bits of the UUID.
+    ///
+    /// It's a lossy mapping : it's stable for a given request id, but two request ids may share the same trace id.
+    /// For an UUID v7, these bits are random, so the upper ones, i.e. the timestamp, are dropped.
+    pub fn to_datadog_trace_id(&self) -> u64 {
+        self.0.as_u64_pair().1
+    }
+}
+
+/// The base62 digits, in ASCII order, so that the base62 forms sort like the UUIDs.
+#[cfg(feature = "short-ids")]
+const BASE62_DIGITS: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The length of the base62 form, i.e. the number of base62 digits of the largest 128-bit value.
+#[cfg(feature = "short-ids")]
+const BASE62_LEN: usize = 22;
+
+#[cfg(feature = "short-ids")]
+impl RequestId {
+    /// Returns the 128-bit value of the request id in base62, left-padded to 22 characters, e.g. for user-facing correlation links.
+    ///
+    /// It's URL-safe, and it sorts like the UUID, so base62 forms of UUIDs v7 sort chronologically too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::now_v7());
+    ///
+    /// assert_eq!(RequestId::from_base62(&request_id.to_base62()), Ok(request_id));
+    /// ```
+    pub fn to_base62(&self) -> String {
+        let mut value = self.0.as_u128();
+        let mut digits = [b'0'; BASE62_LEN];
+
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE62_DIGITS[(value % 62) as usize];
+            value /= 62;
+        }
+
+        // The digits are all ASCII.
+        String::from_utf8(digits.to_vec()).unwrap()
+    }
+
+    /// Parses the base62 form of a request id, as returned by `to_base62`, without validating its version.
+    ///
+    /// It returns `RequestIdError::NotUuid` if it's not 22 base62 digits, or if it overflows 128 bits.
+    pub fn from_base62(base62: &str) -> Result<Self, RequestIdError> {
+        if base62.len() != BASE62_LEN {
+            return Err(RequestIdError::NotUuid);
+        }
+
+        base62
+            .bytes()
+            .try_fold(0u128, |value, byte| {
+                let digit = BASE62_DIGITS.iter().position(|digit| *digit == byte)?;
+
+                value.checked_mul(62)?.checked_add(digit as u128)
+            })
+            .map(|value| Self(Uuid::from_u128(value)))
+            .ok_or(RequestIdError::NotUuid)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl RequestId {
+    /// Returns the request id as a `tracing` field value, recorded as the hyphenated UUID without allocating a string.
+    ///
+    /// `tracing::Value` is sealed, so it can't be implemented for `RequestId` itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::RequestId;
+    /// use uuid::Uuid;
+    ///
+    /// let request_id = RequestId::from(Uuid::now_v7());
+    ///
+    /// let span = tracing::info_span!("request", request_id = tracing::field::Empty);
+    /// span.record("request_id", request_id.as_field());
+    ///
+    /// tracing::info!(request_id = request_id.as_field(), "Handled");
+    /// ```
+    pub fn as_field(&self) -> tracing::field::DisplayValue<uuid::fmt::Hyphenated> {
+        tracing::field::display(self.0.hyphenated())
+    }
+}
+
+/// Builds a `RequestId` from an UUID v7 literal, validated at compile time, e.g. for tests and fixtures.
+///
+/// The literal is parsed in a const block, so an invalid one, or one that isn't an UUID v7, fails the build.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestId, request_id};
+///
+/// const REQUEST_ID: RequestId = request_id!("01965864-f8ab-7eb8-912a-a2c999ab110e");
+///
+/// assert_eq!(REQUEST_ID.to_string(), "01965864-f8ab-7eb8-912a-a2c999ab110e");
+/// ```
+///
+/// An UUID v4 doesn't compile :
+///
+/// ```compile_fail
+/// use request_id_middleware::request_id;
+///
+/// let request_id = request_id!("6edaba95-4f5b-4547-be3f-85210d3ff8bf");
+/// ```
+#[macro_export]
+macro_rules! request_id {
+    ($literal:literal) => {
+        const {
+            match $crate::RequestId::from_literal($literal) {
+                Some(request_id) => request_id,
+                None => panic!(concat!("Invalid request id literal : ", $literal)),
+            }
+        }
+    };
+}
+
+impl From<Uuid> for RequestId {
+    /// Wraps the given UUID without validating its version.
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<RequestId> for Uuid {
+    fn from(request_id: RequestId) -> Self {
+        request_id.0
+    }
+}
+
+/// Formats the request id as an hyphenated lowercase UUID, encoded into a stack buffer, i.e. without any heap allocation.
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut buffer = Uuid::encode_buffer();
+
+        f.write_str(self.0.hyphenated().encode_lower(&mut buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{RequestId, RequestIdConfig, RequestIdError};
+    use axum::http::{Request, StatusCode};
+    use uuid::{Uuid, Variant, Version};
+
+    #[test]
+    fn test_id_resolve() {
+        let parts = |request_id: &str| {
+            Request::builder()
+                .header("x-request-id", request_id)
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+
+        assert_eq!(
+            RequestId::resolve(
+                &parts("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                &RequestIdConfig::default()
+            ),
+            Ok(RequestId::from(
+                Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()
+            ))
+        );
+        assert_eq!(
+            RequestId::resolve(
+                &parts("6edaba95-4f5b-4547-be3f-85210d3ff8bf"),
+                &RequestIdConfig::default()
+            ),
+            Err((StatusCode::BAD_REQUEST, RequestIdError::NotV7))
+        );
+        assert_eq!(
+            RequestId::resolve(
+                &parts("internal-01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                &RequestIdConfig::default().with_reserved_prefixes(vec!["internal-".to_string()])
+            ),
+            Err((StatusCode::FORBIDDEN, RequestIdError::ReservedPrefix))
+        );
+    }
+
+    #[test]
+    fn test_id_display() {
+        let uuid = Uuid::try_parse("01965864-F8AB-7EB8-912A-A2C999AB110E").unwrap();
+
+        assert_eq!(
+            format!("[{}]", RequestId::from(uuid)),
+            "[01965864-f8ab-7eb8-912a-a2c999ab110e]"
+        );
+        assert_eq!(
+            RequestId::from(uuid).to_string(),
+            uuid.hyphenated().to_string()
+        );
+    }
+
+    #[test]
+    fn test_id_from_uuid_v7() {
+        let uuid = Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap();
+
+        assert_eq!(RequestId::from(uuid).as_uuid(), &uuid);
+        assert_eq!(RequestId::try_from_uuid(uuid).unwrap().as_uuid(), &uuid);
+        assert_eq!(
+            RequestId::from(uuid).to_string(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_id_from_uuid_v4() {
+        let uuid = Uuid::try_parse("6edaba95-4f5b-4547-be3f-85210d3ff8bf").unwrap();
+
+        assert_eq!(RequestId::from(uuid).as_uuid(), &uuid);
+        assert_eq!(RequestId::try_from_uuid(uuid), Err(RequestIdError::NotV7));
+    }
+
+    #[test]
+    fn test_id_to_gcp_trace() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(
+            request_id.to_gcp_trace("my-project"),
+            "projects/my-project/traces/01965864f8ab7eb8912aa2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_id_same_id() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert!(request_id.same_id("{01965864-F8AB-7EB8-912A-A2C999AB110E}"));
+        assert!(request_id.same_id("urn:uuid:01965864-f8ab-7eb8-912a-a2c999ab110e"));
+        assert!(request_id.same_id("01965864f8ab7eb8912aa2c999ab110e"));
+        assert!(!request_id.same_id("0196583c-4d2a-7087-9beb-6214d18ec924"));
+    }
+
+    #[test]
+    fn test_id_version_and_variant() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(request_id.version(), Some(Version::SortRand));
+        assert_eq!(request_id.variant(), Variant::RFC4122);
+
+        let request_id =
+            RequestId::from(Uuid::try_parse("6edaba95-4f5b-4547-be3f-85210d3ff8bf").unwrap());
+
+        assert_eq!(request_id.version(), Some(Version::Random));
+    }
+
+    #[test]
+    fn test_id_as_bytes() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(
+            request_id.as_bytes(),
+            [
+                0x01, 0x96, 0x58, 0x64, 0xf8, 0xab, 0x7e, 0xb8, 0x91, 0x2a, 0xa2, 0xc9, 0x99, 0xab,
+                0x11, 0x0e
+            ]
+        );
+    }
+
+    #[test]
+    fn test_id_system_time() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(
+            request_id.system_time(),
+            Some(UNIX_EPOCH + Duration::from_millis(0x01965864f8ab))
+        );
+        assert_eq!(
+            RequestId::from(Uuid::try_parse("6edaba95-4f5b-4547-be3f-85210d3ff8bf").unwrap())
+                .system_time(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_id_from_parts() {
+        let request_id = RequestId::from(Uuid::now_v7());
+        let bytes = request_id.as_bytes();
+
+        let unix_ms = request_id
+            .system_time()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let random: [u8; 10] = bytes[6..].try_into().unwrap();
+
+        let rebuilt = RequestId::from_parts(unix_ms, random);
+
+        assert_eq!(rebuilt, request_id);
+        assert_eq!(rebuilt.system_time(), request_id.system_time());
+        assert_eq!(rebuilt.version(), Some(Version::SortRand));
+        assert_eq!(rebuilt.variant(), Variant::RFC4122);
+
+        let rebuilt = RequestId::from_parts(unix_ms, [0xff; 10]);
+
+        assert_eq!(rebuilt.as_bytes()[..6], bytes[..6]);
+        assert_eq!(
+            rebuilt.as_bytes()[6..],
+            [0x7f, 0xff, 0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_id_within() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+        let close =
+            RequestId::from(Uuid::try_parse("01965864-fa9f-7087-9beb-6214d18ec924").unwrap());
+        let far = RequestId::from(Uuid::try_parse("0196583c-4d2a-7087-9beb-6214d18ec924").unwrap());
+
+        assert!(request_id.within(&close, Duration::from_millis(500)));
+        assert!(close.within(&request_id, Duration::from_millis(500)));
+        assert!(!request_id.within(&close, Duration::from_millis(499)));
+        assert!(!request_id.within(&far, Duration::from_secs(60)));
+        assert!(!far.within(&request_id, Duration::from_secs(60)));
+        assert!(!request_id.within(
+            &RequestId::from(Uuid::try_parse("6edaba95-4f5b-4547-be3f-85210d3ff8bf").unwrap()),
+            Duration::MAX
+        ));
+    }
+
+    #[test]
+    fn test_id_derive_child() {
+        let parent =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+        let child = parent.derive_child();
+
+        assert_eq!(child.as_bytes()[..6], parent.as_bytes()[..6]);
+        assert_ne!(child.as_bytes()[6..], parent.as_bytes()[6..]);
+        assert_eq!(child.system_time(), parent.system_time());
+        assert_eq!(child.version(), Some(Version::SortRand));
+        assert_eq!(child.variant(), Variant::RFC4122);
+    }
+
+    #[test]
+    fn test_id_request_id_macro() {
+        let request_id = crate::request_id!("01965864-f8ab-7eb8-912a-a2c999ab110e");
+
+        assert_eq!(
+            request_id,
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap())
+        );
+        assert_eq!(
+            RequestId::from_literal("6edaba95-4f5b-4547-be3f-85210d3ff8bf"),
+            None
+        );
+        assert_eq!(RequestId::from_literal("foo"), None);
+    }
+
+    #[test]
+    fn test_id_to_sortable_key() {
+        let older =
+            RequestId::from(Uuid::try_parse("0196583c-4d2a-7087-9beb-6214d18ec924").unwrap());
+        let newer =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert!(older.system_time() < newer.system_time());
+        assert!(older.to_sortable_key() < newer.to_sortable_key());
+        assert_eq!(older.to_sortable_key().len(), 32);
+    }
+
+    #[test]
+    fn test_id_sample() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        // The mixed bits are 0x99e5_e3cd_246b_ac7e, i.e. about 0.601 of the 64-bit range.
+        assert!(!request_id.sample(0.6));
+        assert!(request_id.sample(0.61));
+        assert!((0..100).all(|_| request_id.sample(0.61)));
+
+        let sampled = (0..10_000)
+            .filter(|_| RequestId::from(Uuid::now_v7()).sample(0.25))
+            .count();
+
+        assert!((2_000..3_000).contains(&sampled), "{}", sampled);
+    }
+
+    #[test]
+    fn test_id_namespace_bits() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("2ed6657d-e927-568b-95e1-2665a8aea6a2").unwrap());
+
+        assert_eq!(request_id.version(), Some(Version::Sha1));
+        assert_eq!(
+            request_id.namespace_bits(),
+            Some(0x2ed6657d_e927_068b_15e1_2665a8aea6a2)
+        );
+
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(request_id.namespace_bits(), None);
+    }
+
+    #[cfg(feature = "datadog")]
+    #[test]
+    fn test_id_to_datadog_trace_id() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        assert_eq!(request_id.to_datadog_trace_id(), 0x912a_a2c9_99ab_110e);
+        assert_eq!(request_id.to_datadog_trace_id(), 10_460_352_071_271_649_550);
+    }
+
+    #[cfg(feature = "short-ids")]
+    #[test]
+    fn test_id_base62_round_trip() {
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        let base62 = request_id.to_base62();
+
+        assert!(base62.len() < request_id.to_string().len());
+        assert_eq!(RequestId::from_base62(&base62), Ok(request_id));
+        assert_eq!(
+            RequestId::from(Uuid::max()).to_base62(),
+            "7n42DGM5Tflk9n8mt7Fhc7"
+        );
+        assert_eq!(
+            RequestId::from_base62("0000000000000000000000"),
+            Ok(RequestId::from(Uuid::nil()))
+        );
+        assert_eq!(
+            RequestId::from_base62("zzzzzzzzzzzzzzzzzzzzzz"),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            RequestId::from_base62("0000000000000000000-_!"),
+            Err(RequestIdError::NotUuid)
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_id_as_field() {
+        use std::{
+            fmt,
+            sync::{Arc, Mutex},
+        };
+
+        use tracing::{
+            Event, Metadata, Subscriber,
+            field::{Field, Visit},
+            span::{Attributes, Id, Record},
+        };
+
+        struct Recorded(Arc<Mutex<Vec<String>>>);
+
+        impl Visit for Recorded {
+            fn record_debug(&mut self, _: &Field, value: &dyn fmt::Debug) {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, values: &Record<'_>) {
+                values.record(&mut Recorded(self.0.clone()));
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Recorded(self.0.clone()));
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let _guard = tracing::subscriber::set_default(Recorder(recorded.clone()));
+
+        let request_id =
+            RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+
+        let span = tracing::info_span!("request", request_id = tracing::field::Empty);
+        span.record("request_id", request_id.as_field());
+
+        tracing::info!(request_id = request_id.as_field());
+
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![
+                "01965864-f8ab-7eb8-912a-a2c999ab110e",
+                "01965864-f8ab-7eb8-912a-a2c999ab110e"
+            ]
+        );
+    }
+}
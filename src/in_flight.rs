@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use log::warn;
+use tower::{Layer, Service};
+
+use crate::{
+    HEADER_X_REQUEST_ID, RequestIdError, RequestIdRejection, ResolvedRequestId,
+    config::with_default_config,
+};
+
+/// This is a `tower` layer that caps the number of in-flight requests bearing the same request id, e.g. to prevent double-submits.
+///
+/// It must be applied inside a `RequestIdLayer`, since it reads the `ResolvedRequestId` extension.
+/// Only the request ids sent by the client are tracked, since generated ones are unique.
+/// Once the limit is reached, a new request bearing the same request id is rejected as `RequestIdError::InFlight`,
+/// rendered per the process-global configuration set via `set_default_config`, i.e. with a 409 Conflict by default,
+/// and logged per its redaction.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::post, Router};
+/// use request_id_middleware::{InFlightLayer, RequestIdConfig, RequestIdLayer};
+///
+/// let app = Router::<()>::new()
+///     .route("/orders", post(|| async {}))
+///     .layer(InFlightLayer::new())
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InFlightLayer {
+    limit: usize,
+    status: Option<StatusCode>,
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl InFlightLayer {
+    /// Creates the layer, allowing a single in-flight request per request id.
+    pub fn new() -> Self {
+        Self {
+            limit: 1,
+            status: None,
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Sets the maximum number of in-flight requests bearing the same request id (default `1`).
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets the status of the rejected requests, whatever the configured mapping (default `409 Conflict`).
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
+}
+
+impl Default for InFlightLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for InFlightLayer {
+    type Service = InFlightService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InFlightService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// The service produced by `InFlightLayer`.
+#[derive(Debug, Clone)]
+pub struct InFlightService<S> {
+    inner: S,
+    layer: InFlightLayer,
+}
+
+impl<S> Service<Request> for InFlightService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let request_id = request
+            .extensions()
+            .get::<ResolvedRequestId>()
+            .filter(|resolved| !resolved.generated)
+            .map(|resolved| resolved.id.clone());
+
+        let guard = match request_id {
+            Some(request_id) => match InFlightGuard::acquire(&self.layer, request_id) {
+                Some(guard) => Some(guard),
+                None => {
+                    let rejection = with_default_config(|config| {
+                        RequestIdRejection::with_config(
+                            RequestIdError::InFlight,
+                            config,
+                            request.headers(),
+                        )
+                    });
+
+                    let rejection = match self.layer.status {
+                        Some(status) => rejection.with_status(status),
+                        None => rejection,
+                    };
+
+                    return Box::pin(async move { Ok(rejection.into_response()) });
+                }
+            },
+            None => None,
+        };
+
+        // The ready service must be the one called, so keep it and leave the clone in place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let response = inner.call(request).await;
+
+            drop(guard);
+
+            response
+        })
+    }
+}
+
+/// Counts an in-flight request, until it's dropped, i.e. when the response is sent or the request is cancelled.
+struct InFlightGuard {
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+    request_id: String,
+}
+
+impl InFlightGuard {
+    fn acquire(layer: &InFlightLayer, request_id: String) -> Option<Self> {
+        let mut in_flight = layer
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // The entry is only inserted once the request is admitted, so that the rejected ones don't grow the map.
+        if in_flight.get(&request_id).copied().unwrap_or_default() >= layer.limit {
+            warn!(
+                "[{}] Rejected request already in flight : {}",
+                HEADER_X_REQUEST_ID,
+                with_default_config(|config| config
+                    .log_redaction()
+                    .redact(&request_id)
+                    .into_owned())
+            );
+
+            return None;
+        }
+
+        *in_flight.entry(request_id.clone()).or_default() += 1;
+
+        Some(Self {
+            in_flight: layer.in_flight.clone(),
+            request_id,
+        })
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        // It must not panic while dropped, e.g. when the request is cancelled during a panic.
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(count) = in_flight.get_mut(&self.request_id) {
+            *count -= 1;
+
+            if *count == 0 {
+                in_flight.remove(&self.request_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{InFlightLayer, RequestIdConfig, RequestIdLayer};
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tokio::sync::oneshot;
+    use tower::ServiceExt;
+
+    fn request() -> Request<Body> {
+        Request::builder()
+            .uri("/")
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_rejects_concurrent_duplicate() {
+        let (entered, on_entered) = oneshot::channel::<()>();
+        let (release, on_release) = oneshot::channel::<()>();
+        let first_call = Arc::new(Mutex::new(Some((entered, on_release))));
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(move || {
+                    let first_call = first_call.lock().unwrap().take();
+
+                    async move {
+                        // The first request waits in the handler until released.
+                        if let Some((entered, on_release)) = first_call {
+                            entered.send(()).unwrap();
+                            on_release.await.unwrap();
+                        }
+                    }
+                }),
+            )
+            .layer(InFlightLayer::new())
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let first = tokio::spawn(app.clone().oneshot(request()));
+
+        on_entered.await.unwrap();
+
+        let rejected = app.clone().oneshot(request()).await.unwrap();
+
+        assert_eq!(rejected.status(), StatusCode::CONFLICT);
+        assert_eq!(rejected.headers()["x-request-id-error"], "in-flight");
+
+        let body = rejected.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"Invalid X-Request-Id : Already in flight");
+
+        release.send(()).unwrap();
+
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            app.oneshot(request()).await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_rejections_not_tracked() {
+        let layer = InFlightLayer::new().with_limit(0);
+
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(layer.clone())
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        for _ in 0..3 {
+            assert_eq!(
+                app.clone().oneshot(request()).await.unwrap().status(),
+                StatusCode::CONFLICT
+            );
+        }
+
+        assert!(layer.in_flight.lock().unwrap().is_empty());
+    }
+}
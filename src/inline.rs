@@ -0,0 +1,167 @@
+use std::{fmt, ops::Deref};
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    ExtractRequestId, RequestIdRejection, ResolvedRequestId,
+    resolve::debug_assert_single_generation,
+};
+
+/// The capacity of the inline buffer, i.e. the length of the longest accepted form of an UUID, `urn:uuid:UUID`.
+const INLINE_CAPACITY: usize = 45;
+
+/// A request id stored inline, without any heap allocation, when it fits in 45 bytes, i.e. for any accepted UUID form.
+///
+/// A longer request id, e.g. from a custom id fetcher, spills onto the heap.
+#[derive(Clone)]
+pub struct InlineRequestId(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        buffer: [u8; INLINE_CAPACITY],
+        len: u8,
+    },
+    Heap(String),
+}
+
+impl InlineRequestId {
+    /// Stores the given request id.
+    pub fn new(request_id: &str) -> Self {
+        if request_id.len() > INLINE_CAPACITY {
+            return Self(Repr::Heap(request_id.to_string()));
+        }
+
+        let mut buffer = [0; INLINE_CAPACITY];
+        buffer[..request_id.len()].copy_from_slice(request_id.as_bytes());
+
+        Self(Repr::Inline {
+            buffer,
+            len: request_id.len() as u8,
+        })
+    }
+
+    /// Returns the request id as a borrowed string slice.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // The buffer is only ever filled from a whole `&str`.
+            Repr::Inline { buffer, len } => std::str::from_utf8(&buffer[..*len as usize]).unwrap(),
+            Repr::Heap(request_id) => request_id,
+        }
+    }
+
+    /// Returns whether the request id is stored inline, i.e. without any heap allocation.
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+}
+
+impl Deref for InlineRequestId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for InlineRequestId {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for InlineRequestId {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for InlineRequestId {}
+
+impl fmt::Debug for InlineRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for InlineRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// This is a custom extractor for Axum that extracts the request id exactly like `ExtractRequestId`, stored inline.
+///
+/// When a `RequestIdLayer` already resolved the request id, it's copied from the request extensions without any heap allocation.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractInlineRequestId, RequestIdConfig, RequestIdLayer};
+///
+/// async fn handler(ExtractInlineRequestId(request_id): ExtractInlineRequestId) {
+///     println!("Request Id: {}", request_id);
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractInlineRequestId(pub InlineRequestId);
+
+impl<S> FromRequestParts<S> for ExtractInlineRequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(resolved) = parts.extensions.get::<ResolvedRequestId>() {
+            debug_assert_single_generation(&parts.extensions, resolved);
+
+            return Ok(ExtractInlineRequestId(InlineRequestId::new(&resolved.id)));
+        }
+
+        let ExtractRequestId(request_id) =
+            ExtractRequestId::from_request_parts(parts, state).await?;
+
+        Ok(ExtractInlineRequestId(InlineRequestId::new(&request_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtractInlineRequestId, InlineRequestId};
+    use axum::{body::Body, extract::FromRequestParts, http::Request};
+
+    #[tokio::test]
+    async fn test_inline_extractor_holds_value() {
+        let mut parts = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        for _ in 0..2 {
+            let ExtractInlineRequestId(request_id) =
+                ExtractInlineRequestId::from_request_parts(&mut parts, &())
+                    .await
+                    .unwrap();
+
+            assert!(request_id.is_inline());
+            assert_eq!(request_id.as_str(), "01965864-f8ab-7eb8-912a-a2c999ab110e");
+        }
+    }
+
+    #[test]
+    fn test_inline_spills_onto_heap() {
+        let request_id = "urn:uuid:{01965864-f8ab-7eb8-912a-a2c999ab110e}";
+
+        assert!(!InlineRequestId::new(request_id).is_inline());
+        assert_eq!(&*InlineRequestId::new(request_id), request_id);
+        assert!(InlineRequestId::new("").is_inline());
+    }
+}
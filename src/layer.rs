@@ -0,0 +1,1748 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue, header::LOCATION, request::Parts},
+    response::{IntoResponse, Response},
+};
+use log::{error, warn};
+use tower::{Layer, Service, layer::util::Stack};
+use uuid::Uuid;
+
+use crate::{
+    IdSource, Precedence, RequestIdConfig, RequestIdError, RequestIdRejection, ResolutionEvent,
+    ResolutionOutcome, ResolvedRequestId, WriteMode,
+    format::format,
+    generate::generate,
+    raw::RawHeader,
+    resolve::{debug_assert_single_generation, egress_id, mark_generated, resolve_from, validate},
+    sampled::{SamplingDecision, sampling_decision},
+    source::{HEADER_TRACEPARENT, synthesize_traceparent},
+    trailer::TrailerBody,
+};
+
+const HEADER_X_REQUEST_ID_HANDLER: &str = "x-request-id-handler";
+const HEADER_X_REQUEST_ID_WARNING: &str = "x-request-id-warning";
+const HEADER_SERVER_TIMING: &str = "server-timing";
+
+/// This is a `tower` layer that resolves the request id once per request, and makes it available to the whole stack.
+///
+/// In order, unless a previous layer already resolved the request id, it :
+///
+/// - validates the request id from the configured header, or generates (or fetches) a new UUID v7 if it's missing ;
+/// - rejects the request with a 400 Bad Request if the request id is invalid, without calling the inner service,
+///   unless in dry-run mode, where it's replaced by a generated one and flagged by the `X-Request-Id-Warning` response header ;
+/// - rejects the request with a 409 Conflict if the request id sent by the client fails the configured freshness check ;
+/// - inserts the resolved request id into the request extensions as a `ResolvedRequestId`, so that `ExtractRequestId` and the next layers return the same value ;
+/// - optionally injects a `traceparent` request header derived from a generated request id ;
+/// - optionally rewrites the request header to the resolved request id, for handlers reading it directly ;
+/// - runs the inner service inside a `tracing` span carrying the request id (with the `tracing` feature) ;
+/// - runs the inner service with the request id in a task-local, see `current_request_id` (with the `task-local` feature) ;
+/// - echoes the resolved request id on the response header, if the response status matches the configured condition,
+///   including the rejections of other extractors, e.g. `Json`, since it doesn't depend on the handler's extractors ;
+/// - optionally echoes it on the `Server-Timing` response header, too ;
+/// - optionally echoes it as a response trailer, too ;
+/// - optionally inserts the `ResolvedRequestId` into the response extensions ;
+/// - optionally sets a shadow response header to a fresh UUID v7, e.g. during a migration to another id scheme ;
+/// - optionally reads the trace-sampling decision header, and echoes it on the response ;
+/// - optionally adds the `X-Request-Id-Handler` response header, carrying the crate name and version.
+///
+/// When a previous layer already resolved the request id, a different one in the configured header is handled per
+/// `RequestIdConfig::with_extension_vs_header`.
+///
+/// Since the request id is resolved before calling the inner service, an error handler of `HandleErrorLayer` inside this layer
+/// can take `ExtractRequestId` as its first argument, e.g. to include the request id in the error body, and the error response
+/// carries the response header like any other. This layer must therefore be added after the fallible layers with `Router::layer`.
+///
+/// ```rust
+/// use axum::{error_handling::HandleErrorLayer, extract::Request, http::StatusCode, response::Response, BoxError, Router};
+/// use request_id_middleware::{ExtractRequestId, RequestIdConfig, RequestIdLayer};
+/// use tower::{service_fn, ServiceBuilder};
+///
+/// async fn handle_error(ExtractRequestId(request_id): ExtractRequestId, err: BoxError) -> (StatusCode, String) {
+///     (StatusCode::INTERNAL_SERVER_ERROR, format!("[{}] {}", request_id, err))
+/// }
+///
+/// let fallible = service_fn(|_: Request| async { Err::<Response, BoxError>("Boom".into()) });
+///
+/// let app = Router::<()>::new()
+///     .route_service("/foo", ServiceBuilder::new().layer(HandleErrorLayer::new(handle_error)).service(fallible))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractRequestId, RequestIdConfig, RequestIdLayer};
+///
+/// async fn handler(ExtractRequestId(request_id): ExtractRequestId) -> String {
+///     request_id
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestIdLayer {
+    config: Arc<RequestIdConfig>,
+}
+
+impl RequestIdLayer {
+    /// Creates the layer from the given configuration.
+    pub fn new(config: RequestIdConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+
+    /// Stacks the given access-log layer inside this one, so that it finds the `ResolvedRequestId` in the request extensions.
+    ///
+    /// This layer must be the outermost one, since the access-log layer records the request id when it's called.
+    /// With `Router::layer`, that means the access-log layer must be added first, which this helper enforces.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::{extract::Request, middleware::{from_fn, Next}, routing::get, Router};
+    /// use request_id_middleware::{RequestIdConfig, RequestIdLayer, ResolvedRequestId};
+    ///
+    /// async fn access_log(request: Request, next: Next) -> axum::response::Response {
+    ///     let field = request
+    ///         .extensions()
+    ///         .get::<ResolvedRequestId>()
+    ///         .map(|resolved| resolved.access_log_field());
+    ///
+    ///     println!("{} {} {:?}", request.method(), request.uri(), field);
+    ///
+    ///     next.run(request).await
+    /// }
+    ///
+    /// let app = Router::<()>::new()
+    ///     .route("/foo", get(|| async {}))
+    ///     .layer(RequestIdLayer::new(RequestIdConfig::default()).around(from_fn(access_log)));
+    /// ```
+    pub fn around<L>(self, access_log: L) -> Stack<L, Self> {
+        Stack::new(access_log, self)
+    }
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// The service produced by `RequestIdLayer`.
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+    config: Arc<RequestIdConfig>,
+}
+
+impl<S> Service<Request> for RequestIdService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let config = self.config.clone();
+
+        // The ready service must be the one called, so keep it and leave the clone in place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+
+            // A previous layer of this crate already resolved the request id, so it's reused as is.
+            let resolved = parts.extensions.get::<ResolvedRequestId>().map(|resolved| {
+                debug_assert_single_generation(&parts.extensions, resolved);
+
+                resolved.id.clone()
+            });
+
+            let (request_id, warning) = match resolved {
+                Some(resolved) => match reconcile(&mut parts, resolved, &config) {
+                    Ok(request_id) => (request_id, None),
+                    Err(rejection) => return Ok(rejection.into_response()),
+                },
+                None => match resolve_request_id(&mut parts, &config).await {
+                    Ok(resolved) => resolved,
+                    Err(rejection) => return Ok(rejection.into_response()),
+                },
+            };
+
+            if config.synthesize_traceparent()
+                && !parts.headers.contains_key(HEADER_TRACEPARENT)
+                && parts
+                    .extensions
+                    .get::<ResolvedRequestId>()
+                    .is_some_and(|resolved| resolved.generated)
+                && let Ok(uuid) = Uuid::try_parse(&request_id)
+                && let Ok(value) = HeaderValue::from_str(&synthesize_traceparent(&uuid))
+            {
+                parts
+                    .headers
+                    .insert(HeaderName::from_static(HEADER_TRACEPARENT), value);
+            }
+
+            let sampled = match (
+                config.sampling_header(),
+                parts.extensions.get::<SamplingDecision>(),
+            ) {
+                (Some(_), Some(SamplingDecision(sampled))) => Some(*sampled),
+                (Some(_), None) => {
+                    let sampled = sampling_decision(&parts.headers, &config);
+
+                    parts.extensions.insert(SamplingDecision(sampled));
+
+                    Some(sampled)
+                }
+                (None, _) => None,
+            };
+
+            if config.rewrite_request_header()
+                && let Ok(value) = HeaderValue::from_str(&request_id)
+            {
+                // The value sent by the client is kept for `ExtractRawRequestId`, unless a previous layer already did.
+                if let Some(raw) = parts.headers.get(config.header_name())
+                    && parts.extensions.get::<RawHeader>().is_none()
+                {
+                    parts.extensions.insert(RawHeader(raw.clone()));
+                }
+
+                parts.headers.insert(config.header_name().clone(), value);
+            }
+
+            let resolved = config
+                .response_extension()
+                .then(|| parts.extensions.get::<ResolvedRequestId>().cloned())
+                .flatten();
+
+            let request = Request::from_parts(parts, body);
+
+            let call = async {
+                #[cfg(feature = "tracing")]
+                if config.trace_span() {
+                    use crate::SpanField;
+                    use tracing::Instrument;
+
+                    let redacted = config.log_redaction().redact(&request_id);
+
+                    let span = match config.span_field() {
+                        SpanField::RequestId => {
+                            tracing::info_span!("request", request_id = %redacted)
+                        }
+                        SpanField::HttpRequestId => {
+                            tracing::info_span!("request", http.request.id = %redacted)
+                        }
+                        SpanField::ReqId => {
+                            tracing::info_span!("request", req_id = %redacted)
+                        }
+                        SpanField::RequestIdCamelCase => {
+                            tracing::info_span!("request", requestId = %redacted)
+                        }
+                    };
+
+                    return inner.call(request).instrument(span).await;
+                }
+
+                inner.call(request).await
+            };
+
+            #[cfg(feature = "task-local")]
+            let call = crate::task_local::CURRENT_REQUEST_ID.scope(request_id.clone(), call);
+
+            let mut response = call.await?;
+
+            let echoed = egress_id(&request_id, &config);
+
+            if config.echo_response()
+                && config
+                    .echo_on()
+                    .is_none_or(|echo_on| echo_on(response.status()))
+                && let Ok(value) = HeaderValue::from_str(&echoed)
+            {
+                write_echo(response.headers_mut(), &config, value);
+            }
+
+            if config.append_to_location()
+                && response.status().is_redirection()
+                && let Some(location) = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                && let Ok(value) = HeaderValue::from_str(&append_query(
+                    location,
+                    config.header_name().as_str(),
+                    &echoed,
+                ))
+            {
+                response.headers_mut().insert(LOCATION, value);
+            }
+
+            if config.server_timing()
+                && config
+                    .echo_on()
+                    .is_none_or(|echo_on| echo_on(response.status()))
+                && let Ok(value) = HeaderValue::from_str(&format!("reqid;desc=\"{}\"", echoed))
+            {
+                response
+                    .headers_mut()
+                    .append(HeaderName::from_static(HEADER_SERVER_TIMING), value);
+            }
+
+            if let Some(resolved) = resolved {
+                response.extensions_mut().insert(resolved);
+            }
+
+            if let Some(shadow_header) = config.shadow_header()
+                && let Ok(value) = HeaderValue::from_str(&format(
+                    None,
+                    &Uuid::now_v7(),
+                    config.canonical_form(),
+                    config.normalize_case(),
+                ))
+            {
+                response.headers_mut().insert(shadow_header.clone(), value);
+            }
+
+            if let (Some(sampling_header), Some(sampled)) = (config.sampling_header(), sampled) {
+                response.headers_mut().insert(
+                    sampling_header.clone(),
+                    HeaderValue::from_static(if sampled { "1" } else { "0" }),
+                );
+            }
+
+            if let Some(warning) = warning {
+                response.headers_mut().insert(
+                    HeaderName::from_static(HEADER_X_REQUEST_ID_WARNING),
+                    HeaderValue::from_static(warning.code()),
+                );
+            }
+
+            if config.echo_handler_version() {
+                response.headers_mut().insert(
+                    HeaderName::from_static(HEADER_X_REQUEST_ID_HANDLER),
+                    HeaderValue::from_static(concat!(
+                        env!("CARGO_PKG_NAME"),
+                        "/",
+                        env!("CARGO_PKG_VERSION")
+                    )),
+                );
+            }
+
+            if config.echo_in_trailers()
+                && config
+                    .echo_on()
+                    .is_none_or(|echo_on| echo_on(response.status()))
+                && let Ok(value) = HeaderValue::from_str(&echoed)
+            {
+                let header_name = config.header_name().clone();
+
+                response =
+                    response.map(|body| Body::new(TrailerBody::new(body, header_name, value)));
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+/// Writes the echoed request id to the response headers, per the configured write mode.
+pub(crate) fn write_echo(headers: &mut HeaderMap, config: &RequestIdConfig, value: HeaderValue) {
+    match config.response_write_mode() {
+        WriteMode::AppendIfPresent => {
+            headers.append(config.header_name().clone(), value);
+        }
+        WriteMode::SkipIfPresent if headers.contains_key(config.header_name()) => {}
+        _ => {
+            headers.insert(config.header_name().clone(), value);
+        }
+    }
+}
+
+/// Reconciles the request id already resolved by a previous layer with the configured header, per the configured precedence.
+fn reconcile(
+    parts: &mut Parts,
+    request_id: String,
+    config: &RequestIdConfig,
+) -> Result<String, RequestIdRejection> {
+    let precedence = config.extension_vs_header();
+
+    if precedence == Precedence::ExtensionWins {
+        return Ok(request_id);
+    }
+
+    let Some(value) = parts.headers.get(config.header_name()) else {
+        return Ok(request_id);
+    };
+
+    let sent = value
+        .to_str()
+        .map_err(|_| RequestIdError::NotAscii)
+        .and_then(|value| validate(value, config))
+        .map_err(|err| RequestIdRejection::with_config(err, config, &parts.headers))?;
+
+    if sent == request_id {
+        return Ok(request_id);
+    }
+
+    if precedence == Precedence::RejectMismatch {
+        error!(
+            "[{}] Failed to reconcile request id due to : Mismatch with the resolved request id",
+            config.header_name()
+        );
+
+        return Err(RequestIdRejection::with_config(
+            RequestIdError::Mismatch,
+            config,
+            &parts.headers,
+        ));
+    }
+
+    parts.extensions.insert(ResolvedRequestId {
+        id: sent.clone(),
+        generated: false,
+        header_name: config.header_name().clone(),
+    });
+
+    Ok(sent)
+}
+
+/// Appends the given parameter to the query string of an URI reference, before its fragment, e.g. `/login?next=%2F&x-request-id=...`.
+fn append_query(location: &str, key: &str, value: &str) -> String {
+    let (location, fragment) = match location.split_once('#') {
+        Some((location, fragment)) => (location, Some(fragment)),
+        None => (location, None),
+    };
+
+    let separator = match location.split_once('?') {
+        Some((_, "")) => "",
+        Some(_) => "&",
+        None => "?",
+    };
+
+    let mut appended = format!(
+        "{}{}{}",
+        location,
+        separator,
+        form_urlencoded::Serializer::new(String::new())
+            .append_pair(key, value)
+            .finish()
+    );
+
+    if let Some(fragment) = fragment {
+        appended.push('#');
+        appended.push_str(fragment);
+    }
+
+    appended
+}
+
+/// Resolves the request id of a request not seen by any previous layer, and inserts it into its extensions, along with the resolution event.
+/// In dry-run mode, an invalid request id is replaced by a generated one, and the reason is returned as a warning.
+async fn resolve_request_id(
+    parts: &mut Parts,
+    config: &RequestIdConfig,
+) -> Result<(String, Option<RequestIdError>), RequestIdRejection> {
+    let started_at = Instant::now();
+    let mut source = None;
+
+    let on_resolution = |outcome, reason: Option<&RequestIdError>, source: Option<IdSource>| {
+        let event = ResolutionEvent {
+            source,
+            outcome,
+            reason: reason.cloned(),
+            latency: started_at.elapsed(),
+        };
+
+        if let Some(on_resolution) = config.on_resolution() {
+            on_resolution(event.clone());
+        }
+
+        event
+    };
+
+    let (request_id, generated, warning) = match check_request_id(parts, config, &mut source).await
+    {
+        Ok((request_id, generated)) => (request_id, generated, None),
+        Err(err) if config.dry_run() => {
+            warn!(
+                "[{}] Accepted invalid request id in dry-run mode due to : {:?}",
+                config.header_name(),
+                err
+            );
+
+            (generate(config), true, Some(err))
+        }
+        Err(err) => {
+            on_resolution(ResolutionOutcome::Rejected, Some(&err), source);
+
+            return Err(RequestIdRejection::with_config(err, config, &parts.headers));
+        }
+    };
+
+    let outcome = if generated {
+        ResolutionOutcome::Generated
+    } else {
+        ResolutionOutcome::Accepted
+    };
+
+    let event = on_resolution(outcome, warning.as_ref(), source);
+
+    if generated {
+        mark_generated(&mut parts.extensions, &request_id);
+    }
+
+    let resolved = ResolvedRequestId {
+        id: request_id.clone(),
+        generated,
+        header_name: config.header_name().clone(),
+    };
+
+    if let Some(on_resolved) = config.on_resolved() {
+        on_resolved(&resolved);
+    }
+
+    parts.extensions.insert(resolved);
+    parts.extensions.insert(event);
+
+    Ok((request_id, warning))
+}
+
+/// Resolves the request id, fetching a new one if configured, and runs the freshness check on a request id sent by the client.
+async fn check_request_id(
+    parts: &Parts,
+    config: &RequestIdConfig,
+    source: &mut Option<IdSource>,
+) -> Result<(String, bool), RequestIdError> {
+    let (mut request_id, generated) = resolve_from(parts, config, source)?;
+
+    if generated && let Some(id_fetcher) = config.id_fetcher() {
+        match id_fetcher().await {
+            Ok(fetched) => request_id = fetched,
+            Err(err) if config.id_fetcher_fallback() => warn!(
+                "[{}] Falling back to a generated request id due to : {:?}",
+                config.header_name(),
+                err
+            ),
+            Err(err) => {
+                error!(
+                    "[{}] Failed to fetch a new request id due to : {:?}",
+                    config.header_name(),
+                    err
+                );
+
+                return Err(RequestIdError::FetchFailed);
+            }
+        }
+    }
+
+    if !generated
+        && let Some(freshness_check) = config.freshness_check()
+        && !freshness_check(request_id.clone()).await
+    {
+        return Err(RequestIdError::AlreadySeen);
+    }
+
+    Ok((request_id, generated))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        CanonicalForm, Enforcement, ExtractRequestId, IdSource, LetterCase, Precedence, RequestId,
+        RequestIdConfig, RequestIdError, RequestIdLayer, ResolutionOutcome, ResolvedRequestId,
+        WriteMode, resolution_from_extensions,
+    };
+    use axum::{
+        BoxError, Extension, Json, Router,
+        body::Body,
+        error_handling::HandleErrorLayer,
+        http::{HeaderMap, HeaderName, Request, StatusCode},
+        middleware::{Next, from_fn},
+        response::Response,
+        routing::{get, post},
+    };
+    use http_body_util::BodyExt;
+    use tower::{ServiceBuilder, ServiceExt, service_fn};
+    use uuid::{Uuid, Version};
+
+    async fn handler(ExtractRequestId(request_id): ExtractRequestId) -> String {
+        request_id
+    }
+
+    async fn echo_on(status: StatusCode) -> bool {
+        let app = Router::new()
+            .route("/", get(move || async move { status }))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_echo_on(|status| !status.is_server_error()),
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        response.headers().contains_key("x-request-id")
+    }
+
+    async fn extension_vs_header(precedence: Precedence) -> (StatusCode, String) {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_extension_vs_header(precedence),
+            ))
+            .layer(from_fn(
+                |mut request: Request<Body>, next: Next| async move {
+                    request.extensions_mut().insert(ResolvedRequestId {
+                        id: "0196583c-4d2a-7087-9beb-6214d18ec924".to_string(),
+                        generated: false,
+                        header_name: HeaderName::from_static("x-request-id"),
+                    });
+
+                    next.run(request).await
+                },
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn single_generation(generated: bool) -> String {
+        let app = Router::new()
+            .route("/", get(handler))
+            // A middleware resolving the request id by itself, again, after the layer generated one.
+            .layer(from_fn(
+                move |mut request: Request<Body>, next: Next| async move {
+                    request.extensions_mut().insert(ResolvedRequestId {
+                        id: "0196583c-4d2a-7087-9beb-6214d18ec924".to_string(),
+                        generated,
+                        header_name: HeaderName::from_static("x-request-id"),
+                    });
+
+                    next.run(request).await
+                },
+            ))
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_layer_single_generation_sent() {
+        assert_eq!(
+            single_generation(false).await,
+            "0196583c-4d2a-7087-9beb-6214d18ec924"
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Two distinct request ids were generated for the same request")]
+    async fn test_layer_single_generation_distinct_ids() {
+        single_generation(true).await;
+    }
+
+    #[tokio::test]
+    async fn test_layer_extension_vs_header() {
+        assert_eq!(
+            extension_vs_header(Precedence::ExtensionWins).await,
+            (
+                StatusCode::OK,
+                "0196583c-4d2a-7087-9beb-6214d18ec924".to_string()
+            )
+        );
+        assert_eq!(
+            extension_vs_header(Precedence::HeaderWins).await,
+            (
+                StatusCode::OK,
+                "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()
+            )
+        );
+        assert_eq!(
+            extension_vs_header(Precedence::RejectMismatch).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid X-Request-Id : Mismatch with the resolved request id".to_string()
+            )
+        );
+    }
+
+    async fn response_write_mode(response_write_mode: WriteMode) -> Vec<String> {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { [("x-request-id", "0196583c-4d2a-7087-9beb-6214d18ec924")] }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_response_write_mode(response_write_mode),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        response
+            .headers()
+            .get_all("x-request-id")
+            .iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_layer_response_write_mode() {
+        assert_eq!(
+            response_write_mode(WriteMode::Overwrite).await,
+            vec!["01965864-f8ab-7eb8-912a-a2c999ab110e"]
+        );
+        assert_eq!(
+            response_write_mode(WriteMode::AppendIfPresent).await,
+            vec![
+                "0196583c-4d2a-7087-9beb-6214d18ec924",
+                "01965864-f8ab-7eb8-912a-a2c999ab110e"
+            ]
+        );
+        assert_eq!(
+            response_write_mode(WriteMode::SkipIfPresent).await,
+            vec!["0196583c-4d2a-7087-9beb-6214d18ec924"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_handle_error() {
+        async fn handle_error(
+            ExtractRequestId(request_id): ExtractRequestId,
+            err: BoxError,
+        ) -> (StatusCode, String) {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("[{}] {}", request_id, err),
+            )
+        }
+
+        let failing =
+            service_fn(|_: Request<Body>| async { Err::<Response, BoxError>("Boom".into()) });
+
+        let app = Router::new()
+            .route_service(
+                "/",
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_error))
+                    .service(failing),
+            )
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers()["x-request-id"],
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(body, "[01965864-f8ab-7eb8-912a-a2c999ab110e] Boom");
+    }
+
+    async fn redirect(append_to_location: bool, location: &'static str) -> Response {
+        let app = Router::new()
+            .route(
+                "/",
+                get(move || async move { (StatusCode::FOUND, [("location", location)]) }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_append_to_location(append_to_location),
+            ));
+
+        app.oneshot(
+            Request::builder()
+                .uri("/")
+                .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_layer_redirect() {
+        let response = redirect(false, "/login?next=%2F").await;
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            response.headers()["x-request-id"],
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+        assert_eq!(response.headers()["location"], "/login?next=%2F");
+
+        for (location, appended) in [
+            (
+                "/login?next=%2F",
+                "/login?next=%2F&x-request-id=01965864-f8ab-7eb8-912a-a2c999ab110e",
+            ),
+            (
+                "https://example.com/login#top",
+                "https://example.com/login?x-request-id=01965864-f8ab-7eb8-912a-a2c999ab110e#top",
+            ),
+        ] {
+            let response = redirect(true, location).await;
+
+            assert_eq!(
+                response.headers()["x-request-id"],
+                "01965864-f8ab-7eb8-912a-a2c999ab110e"
+            );
+            assert_eq!(response.headers()["location"], appended);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer_shadow_header() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_sources(vec![IdSource::AmznRequestId])
+                    .with_header_name(HeaderName::from_static("x-amzn-requestid"))
+                    .with_normalize_case(LetterCase::Preserve)
+                    .with_shadow_header(HeaderName::from_static("x-request-id-v7")),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-amzn-requestid", "4442587FB7D0A2F9")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()["x-amzn-requestid"], "4442587FB7D0A2F9");
+        assert_eq!(
+            Uuid::try_parse(response.headers()["x-request-id-v7"].to_str().unwrap())
+                .unwrap()
+                .get_version(),
+            Some(Version::SortRand)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_echo_on_policy() {
+        assert!(echo_on(StatusCode::OK).await);
+        assert!(echo_on(StatusCode::NOT_FOUND).await);
+        assert!(!echo_on(StatusCode::INTERNAL_SERVER_ERROR).await);
+    }
+
+    #[tokio::test]
+    async fn test_layer_echo_disabled() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_echo_response(false),
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("x-request-id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_layer_echo_handler_version() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_echo_handler_version(true),
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-request-id-handler").unwrap(),
+            &format!("request_id_middleware/{}", crate::version())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_freshness_check_conflict() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_freshness_check(|_| Box::pin(async { false })),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_layer_chained_reads_previous_context() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(
+                    |Extension(resolved): Extension<ResolvedRequestId>| async move {
+                        format!("{} {}", resolved.id, resolved.header_name)
+                    },
+                ),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_header_name(HeaderName::from_static("x-trace-request-id")),
+            ))
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-trace-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(
+            &body[..],
+            b"01965864-f8ab-7eb8-912a-a2c999ab110e x-request-id"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_enforcement_warn_only() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_enforcement(Enforcement::WarnOnly),
+            ));
+
+        for request_id in ["foo", "6edaba95-4f5b-4547-be3f-85210d3ff8bf"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/")
+                        .header("X-Request-Id", request_id)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(response.headers().get("x-request-id-warning").is_none());
+            assert!(
+                RequestId::try_from_uuid(
+                    Uuid::try_parse(response.headers()["x-request-id"].to_str().unwrap()).unwrap()
+                )
+                .is_ok()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer_dry_run_warning() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_dry_run(true),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "6edaba95-4f5b-4547-be3f-85210d3ff8bf")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("x-request-id-warning").unwrap(),
+            "not-a-v7"
+        );
+        assert_ne!(
+            response.headers().get("x-request-id").unwrap(),
+            "6edaba95-4f5b-4547-be3f-85210d3ff8bf"
+        );
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("x-request-id-warning").is_none());
+    }
+
+    async fn fetched(config: RequestIdConfig) -> (StatusCode, Option<String>) {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(config));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        (
+            response.status(),
+            response
+                .headers()
+                .get("x-request-id")
+                .map(|value| value.to_str().unwrap().to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_layer_id_fetcher() {
+        let config = RequestIdConfig::default().with_id_fetcher(|| {
+            Box::pin(async { Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()) })
+        });
+
+        assert_eq!(
+            fetched(config).await,
+            (
+                StatusCode::OK,
+                Some("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_id_fetcher_failure() {
+        let failing = || {
+            RequestIdConfig::default().with_id_fetcher(|| Box::pin(async { Err("down".into()) }))
+        };
+
+        let (status, request_id) = fetched(failing()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(Uuid::try_parse(&request_id.unwrap()).is_ok());
+
+        let (status, _) = fetched(failing().with_id_fetcher_fallback(false)).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_layer_server_timing() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_echo_response(false)
+                    .with_server_timing(true),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("server-timing").unwrap(),
+            r#"reqid;desc="01965864-f8ab-7eb8-912a-a2c999ab110e""#
+        );
+        assert!(response.headers().get("x-request-id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_layer_rewrite_request_header() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|headers: HeaderMap| async move {
+                    headers["x-request-id"].to_str().unwrap().to_string()
+                }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_canonical_form(CanonicalForm::Hyphenated)
+                    .with_rewrite_request_header(true),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "{01965864-F8AB-7EB8-912A-A2C999AB110E}")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+
+    #[tokio::test]
+    async fn test_layer_synthesize_traceparent() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|headers: HeaderMap| async move {
+                    headers["traceparent"].to_str().unwrap().to_string()
+                }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_synthesize_traceparent(true),
+            ));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let request_id = Uuid::try_parse(response.headers()["x-request-id"].to_str().unwrap())
+            .unwrap()
+            .simple()
+            .to_string();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let traceparent = String::from_utf8(body.to_vec()).unwrap();
+        let fields = traceparent.split('-').collect::<Vec<_>>();
+
+        assert_eq!(
+            fields,
+            vec!["00", request_id.as_str(), &request_id[16..], "01"]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_layer_span_field() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::SpanField;
+        use tracing::{
+            Event, Metadata, Subscriber,
+            span::{Attributes, Id, Record},
+        };
+
+        struct FieldNames(Arc<Mutex<Vec<&'static str>>>);
+
+        impl Subscriber for FieldNames {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .extend(span.metadata().fields().iter().map(|field| field.name()));
+
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        for (span_field, name) in [
+            (SpanField::HttpRequestId, "http.request.id"),
+            (SpanField::ReqId, "req_id"),
+            (SpanField::RequestIdCamelCase, "requestId"),
+        ] {
+            let field_names = Arc::new(Mutex::new(Vec::new()));
+            let _guard = tracing::subscriber::set_default(FieldNames(field_names.clone()));
+
+            let app = Router::new()
+                .route("/", get(handler))
+                .layer(RequestIdLayer::new(
+                    RequestIdConfig::default().with_span_field(span_field),
+                ));
+
+            app.oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(*field_names.lock().unwrap(), vec![name]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer_on_resolution_rejected() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new({
+                let events = events.clone();
+
+                RequestIdConfig::default()
+                    .with_on_resolution(move |event| events.lock().unwrap().push(event))
+            }));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "6edaba95-4f5b-4547-be3f-85210d3ff8bf")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let events = events.lock().unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].source,
+            Some(IdSource::Header(HeaderName::from_static("x-request-id")))
+        );
+        assert_eq!(events[0].outcome, ResolutionOutcome::Rejected);
+        assert_eq!(events[0].reason, Some(RequestIdError::NotV7));
+        assert!(events[0].latency < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_layer_id_prefix() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_id_prefix("svc-"),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "svc-01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()["x-request-id"],
+            "svc-01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"01965864-f8ab-7eb8-912a-a2c999ab110e");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "svc-6edaba95-4f5b-4547-be3f-85210d3ff8bf")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let echoed = response.headers()["x-request-id"].to_str().unwrap();
+
+        assert_eq!(
+            Uuid::try_parse(echoed.strip_prefix("svc-").unwrap())
+                .unwrap()
+                .get_version(),
+            Some(Version::SortRand)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_id_prefix_egress() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { (StatusCode::FOUND, [("location", "/login")]) }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_id_prefix("svc-")
+                    .with_append_to_location(true)
+                    .with_server_timing(true),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", "svc-01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers()["x-request-id"],
+            "svc-01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+        assert_eq!(
+            response.headers()["location"],
+            "/login?x-request-id=svc-01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+        assert_eq!(
+            response.headers()["server-timing"],
+            "reqid;desc=\"svc-01965864-f8ab-7eb8-912a-a2c999ab110e\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_resolution_in_extensions() {
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+
+        let log_outcome = {
+            let outcomes = outcomes.clone();
+
+            from_fn(move |request: axum::extract::Request, next: Next| {
+                let event = resolution_from_extensions(request.extensions());
+
+                outcomes
+                    .lock()
+                    .unwrap()
+                    .push(event.map(|event| (event.outcome, event.source.clone())));
+
+                next.run(request)
+            })
+        };
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(log_outcome)
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        for request_id in [Some("01965864-f8ab-7eb8-912a-a2c999ab110e"), None] {
+            let mut request = Request::builder().uri("/");
+
+            if let Some(request_id) = request_id {
+                request = request.header("X-Request-Id", request_id);
+            }
+
+            app.clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                Some((
+                    ResolutionOutcome::Accepted,
+                    Some(IdSource::Header(HeaderName::from_static("x-request-id")))
+                )),
+                Some((ResolutionOutcome::Generated, None)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_on_resolved() {
+        let resolved = Arc::new(Mutex::new(Vec::new()));
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new({
+                let resolved = resolved.clone();
+
+                RequestIdConfig::default().with_on_resolved(move |request_id| {
+                    resolved.lock().unwrap().push(request_id.clone());
+                })
+            }));
+
+        app.oneshot(
+            Request::builder()
+                .uri("/")
+                .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *resolved.lock().unwrap(),
+            vec![ResolvedRequestId {
+                id: "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                generated: false,
+                header_name: HeaderName::from_static("x-request-id"),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_around_access_log() {
+        let fields = Arc::new(Mutex::new(Vec::new()));
+
+        let access_log = {
+            let fields = fields.clone();
+
+            from_fn(move |request: axum::extract::Request, next: Next| {
+                let field = request
+                    .extensions()
+                    .get::<ResolvedRequestId>()
+                    .map(|resolved| {
+                        let (key, value) = resolved.access_log_field();
+
+                        (key.to_string(), value.to_string())
+                    });
+
+                fields.lock().unwrap().push(field);
+
+                next.run(request)
+            })
+        };
+
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(RequestIdConfig::default()).around(access_log));
+
+        app.oneshot(
+            Request::builder()
+                .uri("/")
+                .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *fields.lock().unwrap(),
+            vec![Some((
+                "request_id".to_string(),
+                "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()
+            ))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_reserved_prefixes() {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_reserved_prefixes(vec!["internal-".to_string()]),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(
+                        "X-Request-Id",
+                        "internal-01965864-f8ab-7eb8-912a-a2c999ab110e",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_layer_echo_on_other_extractor_rejection() {
+        let app = Router::new()
+            .route(
+                "/",
+                post(|Json(body): Json<serde_json::Value>| async move { body.to_string() }),
+            )
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header("Content-Type", "application/json")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::from("{not json"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(response.status().is_client_error());
+        assert_eq!(
+            response.headers().get("x-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_response_extension() {
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_response_extension(true),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.extensions().get::<ResolvedRequestId>(),
+            Some(&ResolvedRequestId {
+                id: "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                generated: false,
+                header_name: HeaderName::from_static("x-request-id"),
+            })
+        );
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let resolved = response.extensions().get::<ResolvedRequestId>().unwrap();
+
+        assert!(resolved.generated);
+        assert_eq!(response.headers()["x-request-id"], resolved.id.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_layer_require_id() {
+        let app = Router::new()
+            .route("/", get(handler).post(handler))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_require_id(|parts| parts.method == "POST"),
+            ));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("x-request-id"));
+    }
+
+    #[tokio::test]
+    async fn test_layer_echo_in_trailers() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    Body::from_stream(futures_util::stream::iter([
+                        Ok::<_, std::convert::Infallible>("foo"),
+                        Ok("bar"),
+                    ]))
+                }),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_echo_in_trailers(true),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let collected = response.into_body().collect().await.unwrap();
+
+        assert_eq!(
+            collected.trailers().unwrap().get("x-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+        assert_eq!(collected.to_bytes(), "foobar");
+    }
+}
@@ -1,15 +1,115 @@
+#[cfg(feature = "b3")]
+mod b3;
+#[cfg(feature = "test-util")]
+mod capture;
+mod clock;
+mod collision;
+mod config;
+mod correlation;
+mod error;
+mod event;
+mod fetcher;
+mod filter;
+mod format;
+mod forwarded;
+mod generate;
+mod generator;
+mod graded;
+mod id;
+mod in_flight;
+mod inline;
+mod layer;
+mod multipart;
+mod pool;
+pub mod prelude;
+mod raw;
+mod redaction;
+mod rejection;
+mod resolve;
+mod sampled;
+mod scoped;
+mod source;
+#[cfg(feature = "task-local")]
+mod task_local;
+mod template;
+mod timed;
+mod trailer;
+
+#[cfg(feature = "b3")]
+pub use b3::ExtractB3RequestId;
+#[cfg(feature = "test-util")]
+pub use capture::{CapturingLayer, CapturingService};
+pub use clock::{Clock, SystemClock};
+pub use collision::CollisionChecker;
+#[cfg(feature = "tracing")]
+pub use config::SpanField;
+pub use config::{
+    BoxError, EchoOn, Enforcement, FreshnessCheck, GenerateTransform, IdFetcher, LogField,
+    MessageProvider, OnClockSkew, OnRejection, OnResolved, Precedence, RandomBitsHook,
+    RequestIdConfig, RequireId, StatusFor, TrustLevel, WriteMode, set_default_config,
+};
+pub use correlation::ExtractIds;
+pub use error::RequestIdError;
+pub use event::{ResolutionEvent, ResolutionOutcome, resolution_from_extensions};
+pub use fetcher::{BlockFetcher, CachedIdFetcher, RefillSpawner};
+pub use filter::{RequestIdFilterLayer, RequestIdFilterService};
+pub use format::{CanonicalForm, LetterCase};
+pub use generate::generate_batch;
+pub use generator::{GenerationStrategy, GeneratorHandle};
+pub use graded::ExtractRequestIdGraded;
+pub use id::RequestId;
+pub use in_flight::{InFlightLayer, InFlightService};
+pub use inline::{ExtractInlineRequestId, InlineRequestId};
+pub use layer::{RequestIdLayer, RequestIdService};
+pub use multipart::ExtractMultipartRequestId;
+pub use pool::IdPool;
+pub use raw::ExtractRawRequestId;
+pub use redaction::Redaction;
+pub use rejection::{JsonRenderer, RejectionRenderer, RequestIdRejection, TextRenderer};
+pub use resolve::{
+    CANONICAL_UUID_LEN, RequestTrailers, ResolvedRequestId, ValidationWarning, canonicalize_batch,
+    ensure_request_id, extract_and_echo, request_id_from_extensions, validate_or_generate,
+    validate_with_warnings,
+};
+pub use sampled::{ExtractRequestIdSampled, SamplingDecision};
+pub use scoped::{DefaultScope, ExtractScopedRequestId, ScopedConfig};
+pub use source::{IdSource, SourceSpec};
+#[cfg(feature = "task-local")]
+pub use task_local::{RequestIdGuard, current_request_id};
+pub use template::IdTemplate;
+pub use timed::ExtractRequestIdTimed;
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    convert::Infallible,
+    fmt,
+    sync::{Mutex, PoisonError},
+};
+
 use axum::{
     extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
+    http::{HeaderValue, request::Parts},
+    response::{IntoResponseParts, ResponseParts},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::with_default_config,
+    resolve::{debug_assert_single_generation, egress_id, mark_generated, resolve},
 };
-use log::error;
-use uuid::{Uuid, Version};
 
 /// This is a custom extractor for Axum that extracts the request id, via the `X-Request-Id` header.
 /// If the `X-Request-Id` header is present and it's a valid UUID v7, it returns it.
-/// If the `X-Request-Id` header is present and it's an invalid UUID v7 (either not an UUID or an UUID v7), it returns a 400 Bad Request error with a specific message.
+/// If the `X-Request-Id` header is present and it's an invalid UUID v7 (either not ASCII, not an UUID or not an UUID v7), it returns a 400 Bad Request error with a specific message.
 /// If the `X-Request-Id` header is not present, it defaults to a newly generated UUID v7.
 ///
+/// If a `RequestIdLayer` already resolved the request id, it returns the same value.
+/// Otherwise, it uses the process-global configuration set via `set_default_config`, or the default one.
+///
+/// It also implements `FromRequest`, via the Axum blanket implementation for `FromRequestParts`, so it can be the last extractor of a handler.
+/// A handler taking the whole `Request`, e.g. to consume the body, extracts it with `RequestExt::extract_parts`, which leaves the body untouched.
+///
 /// # Links
 ///
 /// https://docs.rs/axum/latest/axum/index.html
@@ -32,67 +132,318 @@ use uuid::{Uuid, Version};
 ///
 /// let app = Router::<()>::new().route("/foo", get(handler));
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Its `Debug` output tells whether the request id was generated, e.g. `ExtractRequestId("...", generated=true)`,
+/// per the last extraction of that request id, among the last 1024 generated ones.
+#[must_use]
+#[derive(Clone)]
 pub struct ExtractRequestId(pub String);
 
 const HEADER_X_REQUEST_ID: &str = "X-Request-Id";
 
+/// The number of generated request ids remembered for the `Debug` output of `ExtractRequestId`.
+const GENERATED_IDS_CAPACITY: usize = 1_024;
+
+/// The last request ids generated for the extracted requests, oldest first.
+static GENERATED_IDS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Records whether an extracted request id was generated, forgetting the oldest generated one beyond the capacity.
+fn record_provenance(request_id: &str, generated: bool) {
+    let mut generated_ids = GENERATED_IDS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    // A request id sent again by a client, e.g. after it was echoed, isn't generated anymore.
+    if let Some(index) = generated_ids.iter().position(|id| id == request_id) {
+        generated_ids.remove(index);
+    }
+
+    if !generated {
+        return;
+    }
+
+    if generated_ids.len() == GENERATED_IDS_CAPACITY {
+        generated_ids.pop_front();
+    }
+
+    generated_ids.push_back(request_id.to_string());
+}
+
+/// Returns whether the request id is one of the remembered generated ones.
+fn is_generated(request_id: &str) -> bool {
+    GENERATED_IDS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .any(|generated| generated == request_id)
+}
+
+impl fmt::Debug for ExtractRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ExtractRequestId({:?}, generated={})",
+            self.0,
+            is_generated(&self.0)
+        )
+    }
+}
+
+impl ExtractRequestId {
+    /// Returns the request id as a borrowed string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the request id as a `Cow`, borrowed from the extractor without cloning.
+    ///
+    /// Borrowing is safe for as long as the extractor lives, i.e. the whole handler body when it's taken by value.
+    /// Call `into_owned()` on the result, or `into_inner()` on the extractor, to keep the request id beyond that.
+    pub fn as_cow(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.0)
+    }
+
+    /// Returns whether the given value denotes the same request id, whatever its form or case.
+    ///
+    /// Both values are parsed as UUIDs, e.g. `{UUID}`, `urn:uuid:UUID`, hyphen-less and uppercase forms,
+    /// and their 128-bit values are compared. If one of them is not an UUID, it returns `false`.
+    pub fn same_id(&self, other: &str) -> bool {
+        match (
+            Uuid::try_parse(self.0.trim()),
+            Uuid::try_parse(other.trim()),
+        ) {
+            (Ok(uuid), Ok(other)) => uuid == other,
+            _ => false,
+        }
+    }
+
+    /// Consumes the extractor and returns the request id, without cloning.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl AsRef<str> for ExtractRequestId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ExtractRequestId {
+    /// Extracts the request id synchronously, exactly like the `FromRequestParts` implementation does.
+    ///
+    /// The validation and generation core is fully synchronous and doesn't require any async runtime,
+    /// so it can be used from sync contexts or from any runtime (tokio, smol, async-std, ...).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use axum::http::Request;
+    /// use request_id_middleware::ExtractRequestId;
+    ///
+    /// let (mut parts, _) = Request::new(()).into_parts();
+    ///
+    /// let ExtractRequestId(request_id) = ExtractRequestId::from_parts(&mut parts).unwrap();
+    /// ```
+    ///
+    /// The freshness check set via `RequestIdConfig::with_freshness_check` is async, so it's skipped here.
+    pub fn from_parts(parts: &mut Parts) -> Result<Self, RequestIdRejection> {
+        Self::resolve_parts(parts).map(|(request_id, _)| request_id)
+    }
+
+    /// Resolves the request id, and returns whether it was sent by the client in this very call.
+    fn resolve_parts(parts: &mut Parts) -> Result<(Self, bool), RequestIdRejection> {
+        if let Some(resolved) = parts.extensions.get::<ResolvedRequestId>() {
+            debug_assert_single_generation(&parts.extensions, resolved);
+
+            record_provenance(&resolved.id, resolved.generated);
+
+            return Ok((ExtractRequestId(resolved.id.clone()), false));
+        }
+
+        let (request_id, generated, header_name) = with_default_config(|config| {
+            resolve(parts, config)
+                .map(|(request_id, generated)| {
+                    (request_id, generated, config.header_name().clone())
+                })
+                .map_err(|err| RequestIdRejection::with_config(err, config, &parts.headers))
+        })?;
+
+        if generated {
+            mark_generated(&mut parts.extensions, &request_id);
+        }
+
+        record_provenance(&request_id, generated);
+
+        // Later extractions for the same request, e.g. by another extractor, return the same value.
+        parts.extensions.insert(ResolvedRequestId {
+            id: request_id.clone(),
+            generated,
+            header_name,
+        });
+
+        Ok((ExtractRequestId(request_id), !generated))
+    }
+}
+
+/// Extracts the request id like `ExtractRequestId::from_parts`, as a `RequestId`, e.g. from a custom `Handler` implementation
+/// or a generic adapter that can't rely on the extractor traits.
+///
+/// It's resolved per the process-global configuration, or reused from a previous layer, and inserted into the extensions.
+/// A request id that isn't an UUID, e.g. per `RequestIdConfig::with_generation_template`, is rejected as `RequestIdError::NotUuid`.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::Request;
+/// use request_id_middleware::request_id_from_parts;
+///
+/// let (mut parts, _) = Request::new(()).into_parts();
+///
+/// let request_id = request_id_from_parts(&mut parts).unwrap();
+///
+/// assert_eq!(request_id_from_parts(&mut parts).unwrap(), request_id);
+/// ```
+pub fn request_id_from_parts(parts: &mut Parts) -> Result<RequestId, RequestIdRejection> {
+    let ExtractRequestId(request_id) = ExtractRequestId::from_parts(parts)?;
+
+    Uuid::try_parse(&request_id)
+        .map(RequestId::from)
+        .map_err(|_| RequestIdRejection::from(RequestIdError::NotUuid))
+}
+
 impl<S> FromRequestParts<S> for ExtractRequestId
 where
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, String);
+    type Rejection = RequestIdRejection;
 
     async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let request_id = parts.headers.get(HEADER_X_REQUEST_ID);
-
-        match request_id {
-            Some(request_id) => {
-                let request_id = request_id.to_str().unwrap().trim().to_lowercase();
-                let parsed_request_id = Uuid::try_parse(request_id.as_str());
-
-                if parsed_request_id.is_err() {
-                    error!(
-                        "[{}] Failed to parse UUID due to : {:?}",
-                        HEADER_X_REQUEST_ID,
-                        parsed_request_id.err().unwrap()
-                    );
-
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        format!("Invalid {} : Not a valid UUID", HEADER_X_REQUEST_ID),
-                    ));
-                }
-
-                let request_id_version = parsed_request_id.unwrap().get_version().unwrap();
-
-                if request_id_version != Version::SortRand {
-                    error!(
-                        "[{}] Failed to validate UUID due to : Version is {:?}",
-                        HEADER_X_REQUEST_ID, request_id_version
-                    );
-
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        format!("Invalid {} : Not an UUID v7", HEADER_X_REQUEST_ID),
-                    ));
-                }
-
-                Ok(ExtractRequestId(request_id))
+        let (request_id, sent) = Self::resolve_parts(parts)?;
+
+        if sent {
+            let freshness_check = with_default_config(|config| config.freshness_check().cloned());
+
+            if let Some(freshness_check) = freshness_check
+                && !freshness_check(request_id.0.clone()).await
+            {
+                parts.extensions.remove::<ResolvedRequestId>();
+
+                return Err(with_default_config(|config| {
+                    RequestIdRejection::with_config(
+                        RequestIdError::AlreadySeen,
+                        config,
+                        &parts.headers,
+                    )
+                }));
             }
-            None => Ok(ExtractRequestId(Uuid::now_v7().to_string())),
         }
+
+        Ok(request_id)
     }
 }
 
+/// Sets the request id on the response header, so that a handler can return `(request_id, body)`.
+///
+/// The header is the one of the process-global configuration, i.e. `X-Request-Id` by default, and the request id is prefixed
+/// per its `RequestIdConfig::with_id_prefix`, like the layer echoes it.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::ExtractRequestId;
+///
+/// async fn handler(request_id: ExtractRequestId) -> (ExtractRequestId, &'static str) {
+///     (request_id, "Hello, World!")
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+impl IntoResponseParts for ExtractRequestId {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let echoed = with_default_config(|config| {
+            HeaderValue::from_str(&egress_id(&self.0, config))
+                .map(|value| (config.header_name().clone(), value))
+        });
+
+        if let Ok((header_name, value)) = echoed {
+            res.headers_mut().insert(header_name, value);
+        }
+
+        Ok(res)
+    }
+}
+
+/// Returns the version of this crate, e.g. to audit which one is deployed across a fleet.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(request_id_middleware::version(), env!("CARGO_PKG_VERSION"));
+/// ```
+pub fn version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Builds the full request id stack in one call, to be applied via a single `.layer(...)`.
+///
+/// The returned layer generates or validates the request id, inserts it into the request extensions,
+/// runs the inner service inside a `tracing` span (with the `tracing` feature) and echoes the request id on the response.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractRequestId, RequestIdConfig};
+///
+/// async fn handler(ExtractRequestId(request_id): ExtractRequestId) -> String {
+///     request_id
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(request_id_middleware::layered(RequestIdConfig::default()));
+/// ```
+pub fn layered(config: RequestIdConfig) -> RequestIdLayer {
+    RequestIdLayer::new(config)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{ExtractRequestId, HEADER_X_REQUEST_ID};
+    use crate::{ExtractRequestId, HEADER_X_REQUEST_ID, RequestIdError, request_id_from_parts};
     use axum::{
+        RequestExt, Router,
         body::Body,
-        extract::FromRequestParts,
+        extract::{FromRequest, FromRequestParts},
         http::{Request, StatusCode},
+        routing::{get, post},
     };
+    use http_body_util::BodyExt;
+    use std::borrow::Cow;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_lib_extract_request_id_debug() {
+        let (mut parts, _) = Request::builder()
+            .header("x-request-id", "0196a1b2-c3d4-7e5f-8a6b-7c8d9e0f1a2b")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(
+            format!("{:?}", ExtractRequestId::from_parts(&mut parts).unwrap()),
+            "ExtractRequestId(\"0196a1b2-c3d4-7e5f-8a6b-7c8d9e0f1a2b\", generated=false)"
+        );
+
+        let (mut parts, _) = Request::new(()).into_parts();
+        let request_id = ExtractRequestId::from_parts(&mut parts).unwrap();
+
+        assert_eq!(
+            format!("{:?}", request_id),
+            format!("ExtractRequestId({:?}, generated=true)", request_id.0)
+        );
+    }
 
     #[tokio::test]
     async fn test_lib_extract_request_id_with_header_ok_one() {
@@ -107,7 +458,7 @@ mod tests {
 
         match request_id {
             Ok(request_id) => assert_eq!(request_id.0, "01965864-f8ab-7eb8-912a-a2c999ab110e"),
-            Err(err) => assert!(false, "Expected a valid request id : {:?}", err),
+            Err(err) => panic!("Expected a valid request id : {:?}", err),
         }
     }
 
@@ -124,7 +475,7 @@ mod tests {
 
         match request_id {
             Ok(request_id) => assert_eq!(request_id.0, "01965864-f8ab-7eb8-912a-a2c999ab110e"),
-            Err(err) => assert!(false, "Expected a valid request id : {:?}", err),
+            Err(err) => panic!("Expected a valid request id : {:?}", err),
         }
     }
 
@@ -140,7 +491,7 @@ mod tests {
         let request_id = ExtractRequestId::from_request_parts(&mut parts.0, &()).await;
 
         match request_id {
-            Ok(_) => assert!(false, "Expected an error"),
+            Ok(_) => panic!("Expected an error"),
             Err(err) => assert_eq!(
                 err,
                 (
@@ -163,7 +514,7 @@ mod tests {
         let request_id = ExtractRequestId::from_request_parts(&mut parts.0, &()).await;
 
         match request_id {
-            Ok(_) => assert!(false, "Expected an error"),
+            Ok(_) => panic!("Expected an error"),
             Err(err) => assert_eq!(
                 err,
                 (
@@ -174,6 +525,142 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_lib_extract_request_id_from_parts_without_runtime() {
+        let request = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap();
+
+        let mut parts = request.into_parts();
+
+        match ExtractRequestId::from_parts(&mut parts.0) {
+            Ok(request_id) => assert_eq!(request_id.0, "01965864-f8ab-7eb8-912a-a2c999ab110e"),
+            Err(err) => panic!("Expected a valid request id : {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_lib_request_id_from_parts() {
+        let (mut parts, _) = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(
+            request_id_from_parts(&mut parts).unwrap().to_string(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let (mut parts, _) = Request::builder()
+            .header("X-Request-Id", "6edaba95-4f5b-4547-be3f-85210d3ff8bf")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert_eq!(
+            request_id_from_parts(&mut parts).unwrap_err().error(),
+            &RequestIdError::NotV7
+        );
+    }
+
+    #[test]
+    fn test_lib_extract_request_id_same_id() {
+        let request_id = ExtractRequestId("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string());
+
+        assert!(request_id.same_id("01965864-f8ab-7eb8-912a-a2c999ab110e"));
+        assert!(request_id.same_id("{01965864-F8AB-7EB8-912A-A2C999AB110E}"));
+        assert!(request_id.same_id("urn:uuid:01965864-f8ab-7eb8-912a-a2c999ab110e"));
+        assert!(request_id.same_id("01965864F8AB7EB8912AA2C999AB110E"));
+        assert!(!request_id.same_id("0196583c-4d2a-7087-9beb-6214d18ec924"));
+        assert!(!request_id.same_id("this-is-not-a-uuid"));
+    }
+
+    #[tokio::test]
+    async fn test_lib_extract_request_id_into_response_parts() {
+        async fn handler(request_id: ExtractRequestId) -> (ExtractRequestId, &'static str) {
+            (request_id, "Hello, World!")
+        }
+
+        let app = Router::new().route("/", get(handler));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.headers().get("x-request-id").unwrap(),
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_lib_version() {
+        assert!(!crate::version().is_empty());
+        assert_eq!(crate::version(), env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_lib_extract_request_id_as_cow_borrowed() {
+        let request_id = ExtractRequestId("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string());
+
+        match request_id.as_cow() {
+            Cow::Borrowed(borrowed) => assert_eq!(borrowed.as_ptr(), request_id.0.as_ptr()),
+            Cow::Owned(_) => panic!("Expected a borrowed request id"),
+        }
+
+        assert_eq!(request_id.as_str(), "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+
+    #[tokio::test]
+    async fn test_lib_extract_request_id_from_request() {
+        let request = Request::builder()
+            .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap();
+
+        let ExtractRequestId(request_id) =
+            ExtractRequestId::from_request(request, &()).await.unwrap();
+
+        assert_eq!(request_id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+
+    #[tokio::test]
+    async fn test_lib_extract_request_id_keeps_body() {
+        async fn handler(mut request: Request<Body>) -> String {
+            let ExtractRequestId(request_id) = request.extract_parts().await.unwrap();
+            let body = request.into_body().collect().await.unwrap().to_bytes();
+
+            format!("{} {}", request_id, String::from_utf8_lossy(&body))
+        }
+
+        let app = Router::new().route("/", post(handler));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+                    .body(Body::from("foo"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(body, "01965864-f8ab-7eb8-912a-a2c999ab110e foo");
+    }
+
     #[tokio::test]
     async fn test_lib_extract_request_id_without_header() {
         let request = Request::builder().body(Body::empty()).unwrap();
@@ -183,8 +670,8 @@ mod tests {
         let request_id = ExtractRequestId::from_request_parts(&mut parts.0, &()).await;
 
         match request_id {
-            Ok(_) => assert!(true),
-            Err(err) => assert!(false, "Expected a valid request id : {:?}", err),
+            Ok(_) => {}
+            Err(err) => panic!("Expected a valid request id : {:?}", err),
         }
     }
 }
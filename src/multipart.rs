@@ -0,0 +1,291 @@
+use axum::{
+    body::{self, Body},
+    extract::{FromRequest, Request},
+    http::{header::CONTENT_TYPE, request::Parts},
+};
+use log::warn;
+
+use crate::{
+    RequestIdConfig, RequestIdRejection, ResolvedRequestId,
+    config::with_default_config,
+    forwarded::is_trusted,
+    resolve::{debug_assert_single_generation, resolve_from, validate},
+};
+
+/// The maximum length of the body read to find the multipart form field, i.e. the default body limit of Axum.
+const MAX_BODY_LEN: usize = 2 * 1024 * 1024;
+
+/// This is a custom extractor for Axum that extracts the request id like `ExtractRequestId`, then from a multipart form field
+/// as a last resort, e.g. `<input type="hidden" name="request_id">` for HTML forms, which can't set headers.
+///
+/// The field, named per `RequestIdConfig::with_multipart_field`, is only read when none of the configured sources carries a value,
+/// and it's validated like them. It consumes the body, so it must be the last extractor of the handler.
+///
+/// Behind a `RequestIdLayer`, the field is read when the layer generated the request id, i.e. no source carried one.
+/// The field then wins for the handler only: the layer already shared and echoed the generated request id.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::post, Router};
+/// use request_id_middleware::ExtractMultipartRequestId;
+///
+/// async fn handler(ExtractMultipartRequestId(request_id): ExtractMultipartRequestId) {
+///     println!("Request Id: {:?}", request_id);
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", post(handler));
+/// ```
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractMultipartRequestId(pub String);
+
+impl<S> FromRequest<S> for ExtractMultipartRequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request(request: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = request.into_parts();
+
+        let config = with_default_config(RequestIdConfig::clone);
+        let reject = |err| RequestIdRejection::with_config(err, &config, &parts.headers);
+
+        if let Some(resolved) = parts.extensions.get::<ResolvedRequestId>() {
+            debug_assert_single_generation(&parts.extensions, resolved);
+
+            if !resolved.generated {
+                return Ok(ExtractMultipartRequestId(resolved.id.clone()));
+            }
+
+            // A previous layer generated the request id since no source carried a value, so the field still may.
+            let generated = resolved.id.clone();
+
+            let field = if is_trusted(&parts, &config) {
+                read_field(&parts, body, &config).await
+            } else {
+                None
+            };
+
+            return match field {
+                Some(field) => validate(&field, &config)
+                    .map(ExtractMultipartRequestId)
+                    .map_err(reject),
+                None => Ok(ExtractMultipartRequestId(generated)),
+            };
+        }
+
+        let mut matched = None;
+        let resolved = resolve_from(&parts, &config, &mut matched);
+
+        let field = match matched {
+            None if is_trusted(&parts, &config) => read_field(&parts, body, &config).await,
+            _ => None,
+        };
+
+        // The request is consumed, so the request id isn't shared with the next extractors.
+        let request_id = match field {
+            Some(field) => validate(&field, &config).map_err(reject)?,
+            None => resolved.map_err(reject)?.0,
+        };
+
+        Ok(ExtractMultipartRequestId(request_id))
+    }
+}
+
+/// Reads the body, and returns the value of the configured field if it's a `multipart/form-data` one carrying it.
+async fn read_field(parts: &Parts, body: Body, config: &RequestIdConfig) -> Option<String> {
+    let content_type = parts.headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let boundary = multipart_boundary(content_type)?;
+
+    let body = match body::to_bytes(body, MAX_BODY_LEN).await {
+        Ok(body) => body,
+        Err(err) => {
+            warn!(
+                "[{}] Ignoring the multipart body due to : {:?}",
+                config.header_name(),
+                err
+            );
+
+            return None;
+        }
+    };
+
+    find_field(
+        &String::from_utf8_lossy(&body),
+        boundary,
+        config.multipart_field(),
+    )
+}
+
+/// Returns the boundary of a `multipart/form-data` content type, e.g. `foo` in `multipart/form-data; boundary="foo"`.
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+
+    if !params
+        .next()?
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+
+    params.find_map(|param| param_value(param, "boundary"))
+}
+
+/// Returns the value of the text field with the given name, per RFC 7578, skipping the file ones.
+fn find_field(body: &str, boundary: &str, name: &str) -> Option<String> {
+    let delimiter = format!("--{}", boundary);
+
+    body.split(delimiter.as_str())
+        .skip(1)
+        .take_while(|part| !part.starts_with("--"))
+        .find_map(|part| {
+            let (headers, content) = part.strip_prefix("\r\n")?.split_once("\r\n\r\n")?;
+
+            let disposition = headers.split("\r\n").find_map(|header| {
+                let (key, value) = header.split_once(':')?;
+
+                key.trim()
+                    .eq_ignore_ascii_case("content-disposition")
+                    .then_some(value)
+            })?;
+
+            let mut params = disposition.split(';').skip(1);
+
+            let named = params
+                .clone()
+                .any(|param| param_value(param, "name") == Some(name));
+            let file = params.any(|param| param_value(param, "filename").is_some());
+
+            (named && !file).then(|| content.strip_suffix("\r\n").unwrap_or(content).to_string())
+        })
+}
+
+/// Returns the value of the given parameter, e.g. `foo` for `name` in `name="foo"`.
+fn param_value<'a>(param: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = param.split_once('=')?;
+
+    name.trim()
+        .eq_ignore_ascii_case(key)
+        .then_some(value.trim().trim_matches('"'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtractMultipartRequestId, RequestIdConfig, RequestIdLayer, RequestIdRejection};
+    use axum::{
+        Router,
+        body::Body,
+        extract::FromRequest,
+        http::{Request, StatusCode},
+        response::IntoResponse,
+        routing::post,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    const BODY: &str = "--foo\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"request_id\"\r\n\
+        \r\n\
+        6edaba95-4f5b-4547-be3f-85210d3ff8bf\r\n\
+        --foo\r\n\
+        Content-Disposition: form-data; name=\"request_id\"\r\n\
+        \r\n\
+        {}\r\n\
+        --foo--\r\n";
+
+    async fn extract(
+        request_id: &str,
+        header: Option<&str>,
+    ) -> Result<ExtractMultipartRequestId, RequestIdRejection> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("content-type", "multipart/form-data; boundary=foo");
+
+        if let Some(header) = header {
+            request = request.header("x-request-id", header);
+        }
+
+        let request = request
+            .body(Body::from(BODY.replace("{}", request_id)))
+            .unwrap();
+
+        ExtractMultipartRequestId::from_request(request, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_multipart_extract_request_id() {
+        assert_eq!(
+            extract("01965864-f8ab-7eb8-912a-a2c999ab110e", None)
+                .await
+                .unwrap(),
+            ExtractMultipartRequestId("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+        );
+        assert_eq!(
+            extract("foo", None)
+                .await
+                .unwrap_err()
+                .into_response()
+                .status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multipart_extract_request_id_header_first() {
+        assert_eq!(
+            extract("foo", Some("0196583c-4d2a-7087-9beb-6214d18ec924"))
+                .await
+                .unwrap(),
+            ExtractMultipartRequestId("0196583c-4d2a-7087-9beb-6214d18ec924".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multipart_extract_request_id_behind_layer() {
+        let app = Router::new()
+            .route(
+                "/",
+                post(
+                    |ExtractMultipartRequestId(request_id): ExtractMultipartRequestId| async move {
+                        request_id
+                    },
+                ),
+            )
+            .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+        let request = |header: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/")
+                .header("content-type", "multipart/form-data; boundary=foo");
+
+            if let Some(header) = header {
+                request = request.header("x-request-id", header);
+            }
+
+            request
+                .body(Body::from(
+                    BODY.replace("{}", "01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                ))
+                .unwrap()
+        };
+
+        // The layer generated a request id, so the field is read.
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"01965864-f8ab-7eb8-912a-a2c999ab110e");
+
+        let response = app
+            .oneshot(request(Some("0196583c-4d2a-7087-9beb-6214d18ec924")))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(&body[..], b"0196583c-4d2a-7087-9beb-6214d18ec924");
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fmt,
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{Receiver, sync_channel},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use uuid::{Timestamp, Uuid};
+
+use crate::generate::MONOTONIC_CONTEXT;
+
+/// The default maximum age of the pooled ids, see `IdPool::with_max_age`.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// A bounded pool of UUIDs v7 pre-generated by a background thread, to move the generation off the hot path.
+///
+/// It's set via `RequestIdConfig::with_id_pool`: each missing request id is then popped from the pool,
+/// or generated as usual if the pool is empty, so that a request never waits for it.
+///
+/// The ids are generated from the monotonic counter, so they're popped in a strictly increasing order.
+/// Their timestamp is the time they were generated, which can be long before the request when the traffic is idle,
+/// so the ids older than the maximum age are discarded when popped, and the background thread refills the pool behind them.
+/// It keeps the pooled ids close to the request time, e.g. for the services checking them with `RequestIdConfig::with_max_id_age`.
+///
+/// The background thread stops once the pool, and all its clones, are dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{IdPool, RequestIdConfig};
+///
+/// let config = RequestIdConfig::default().with_id_pool(IdPool::new(256));
+/// ```
+#[derive(Clone)]
+pub struct IdPool {
+    receiver: Arc<Mutex<Receiver<(Uuid, Instant)>>>,
+    max_age: Duration,
+}
+
+impl IdPool {
+    /// Creates the pool, holding at most `capacity` pre-generated ids, and starts refilling it.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, receiver) = sync_channel(capacity);
+
+        thread::spawn(move || {
+            // Blocks while the pool is full, and stops once the receiver is dropped.
+            while sender
+                .send((
+                    Uuid::new_v7(Timestamp::now(&MONOTONIC_CONTEXT)),
+                    Instant::now(),
+                ))
+                .is_ok()
+            {}
+        });
+
+        Self {
+            receiver: Arc::new(Mutex::new(receiver)),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Sets how long a pre-generated id stays in the pool before it's discarded as stale (default 1 second).
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Pops a pre-generated id, skipping the stale ones, or returns `None` if the pool is empty.
+    pub fn pop(&self) -> Option<Uuid> {
+        let receiver = self.receiver.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // At most the whole pool is stale, so it's bounded by the capacity, and the refilled ids are fresh.
+        while let Ok((uuid, generated_at)) = receiver.try_recv() {
+            if generated_at.elapsed() <= self.max_age {
+                return Some(uuid);
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Debug for IdPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdPool")
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::Arc,
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{IdPool, RequestId, RequestIdConfig, generate::generate};
+    use uuid::{Uuid, Version};
+
+    #[test]
+    fn test_pool_concurrent_unique() {
+        let config = Arc::new(RequestIdConfig::default().with_id_pool(IdPool::new(64)));
+
+        // Lets the background thread fill the pool first.
+        thread::sleep(Duration::from_millis(50));
+
+        let handles = (0..8)
+            .map(|_| {
+                let config = config.clone();
+
+                thread::spawn(move || (0..1_000).map(|_| generate(&config)).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+
+        let request_ids = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(request_ids.len(), 8_000);
+        assert_eq!(request_ids.iter().collect::<HashSet<_>>().len(), 8_000);
+        assert!(request_ids.iter().all(|request_id| {
+            Uuid::try_parse(request_id).unwrap().get_version() == Some(Version::SortRand)
+        }));
+    }
+
+    #[test]
+    fn test_pool_pops_in_order() {
+        let pool = IdPool::new(16);
+
+        thread::sleep(Duration::from_millis(50));
+
+        let request_ids = (0..16).filter_map(|_| pool.pop()).collect::<Vec<_>>();
+
+        assert!(!request_ids.is_empty());
+        assert!(request_ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_pool_discards_stale() {
+        let max_age = Duration::from_millis(20);
+        let pool = IdPool::new(16).with_max_age(max_age);
+
+        // Lets the pool fill up, then its ids grow older than the maximum age.
+        thread::sleep(Duration::from_millis(100));
+
+        let popped_at = SystemTime::now();
+
+        // The stale ids are discarded, so the pool may be empty until the background thread refills it.
+        let request_id = (0..100)
+            .find_map(|_| {
+                pool.pop().or_else(|| {
+                    thread::sleep(Duration::from_millis(1));
+                    None
+                })
+            })
+            .unwrap();
+
+        let generated_at = RequestId::from(request_id).system_time().unwrap();
+
+        // The timestamp has a millisecond precision.
+        assert!(generated_at + Duration::from_millis(1) >= popped_at - max_age);
+    }
+}
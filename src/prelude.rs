@@ -0,0 +1,26 @@
+//! The types needed to wire the request id into an Axum application, re-exported for a single glob import.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use axum::{routing::get, Router};
+//! use request_id_middleware::prelude::*;
+//! use tower::ServiceBuilder;
+//!
+//! async fn handler(ExtractRequestId(request_id): ExtractRequestId) -> String {
+//!     request_id
+//! }
+//!
+//! let app = Router::<()>::new().route("/foo", get(handler)).layer(
+//!     ServiceBuilder::new()
+//!         .layer(RequestIdFilterLayer::new(RequestIdConfig::default()))
+//!         .layer(RequestIdLayer::new(RequestIdConfig::default())),
+//! );
+//!
+//! assert_eq!(RequestIdError::NotUuid.code(), "not-a-uuid");
+//! ```
+
+pub use crate::{
+    ExtractRequestId, RequestId, RequestIdConfig, RequestIdError, RequestIdFilterLayer,
+    RequestIdLayer, RequestIdRejection, ResolvedRequestId,
+};
@@ -0,0 +1,115 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderValue, request::Parts},
+};
+
+use crate::{ExtractRequestId, RequestIdRejection, ResolvedRequestId};
+
+/// The header value sent by the client, kept by the layer before it rewrites the request header.
+#[derive(Debug, Clone)]
+pub(crate) struct RawHeader(pub(crate) HeaderValue);
+
+/// This is a custom extractor for Axum that extracts the request id, exactly like `ExtractRequestId`,
+/// along with the raw header value sent by the client, byte for byte, e.g. to hash it for deduplication.
+///
+/// The validation never mutates the raw value : it's kept even when the layer rewrites the request header,
+/// see `RequestIdConfig::with_rewrite_request_header`. It's `None` when the request id wasn't sent in the configured header,
+/// e.g. when it was generated, or read from another source.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractRawRequestId, RequestIdConfig, RequestIdLayer};
+///
+/// async fn handler(ExtractRawRequestId { id, raw }: ExtractRawRequestId) {
+///     println!("Request Id: {} (sent as {:?})", id, raw);
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractRawRequestId {
+    /// The request id, validated and formatted per the configuration.
+    pub id: String,
+    /// The header value sent by the client, unchanged.
+    pub raw: Option<HeaderValue>,
+}
+
+impl<S> FromRequestParts<S> for ExtractRawRequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractRequestId(id) = ExtractRequestId::from_request_parts(parts, state).await?;
+
+        let raw = match (
+            parts.extensions.get::<RawHeader>(),
+            parts.extensions.get::<ResolvedRequestId>(),
+        ) {
+            (Some(RawHeader(raw)), _) => Some(raw.clone()),
+            (None, Some(resolved)) if !resolved.generated => {
+                parts.headers.get(&resolved.header_name).cloned()
+            }
+            _ => None,
+        };
+
+        Ok(ExtractRawRequestId { id, raw })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CanonicalForm, ExtractRawRequestId, LetterCase, RequestIdConfig, RequestIdLayer};
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderValue, Request},
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_raw_keeps_bytes_sent() {
+        let sent = "{01965864-F8AB-7EB8-912A-A2C999AB110E}";
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(
+                    move |ExtractRawRequestId { id, raw }: ExtractRawRequestId| async move {
+                        assert_eq!(raw, Some(HeaderValue::from_static(sent)));
+                        assert_eq!(raw.unwrap().as_bytes(), sent.as_bytes());
+
+                        id
+                    },
+                ),
+            )
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default()
+                    .with_canonical_form(CanonicalForm::Hyphenated)
+                    .with_normalize_case(LetterCase::Lower)
+                    .with_rewrite_request_header(true),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("x-request-id", sent)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        assert_eq!(body, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+/// How the request id appears in the crate's own log and trace output.
+///
+/// UUIDs v7 embed their generation time, and client-chosen bits, so some deployments don't want them logged as is.
+/// It doesn't change the request id returned by the extractors, nor the one echoed by the layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Redaction {
+    /// The request id is logged as is (default).
+    #[default]
+    None,
+    /// The 16 first hexadecimal characters of the SHA-256 of the request id, stable across requests and processes.
+    Hashed,
+    /// The 8 last characters of the request id, i.e. without its timestamp, e.g. `...99ab110e`.
+    Shortened,
+}
+
+impl Redaction {
+    /// Redacts the given request id.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use request_id_middleware::Redaction;
+    ///
+    /// let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+    ///
+    /// assert_eq!(Redaction::None.redact(request_id), request_id);
+    /// assert_eq!(Redaction::Shortened.redact(request_id), "...99ab110e");
+    /// assert_eq!(Redaction::Hashed.redact(request_id).len(), 16);
+    /// ```
+    pub fn redact<'a>(&self, request_id: &'a str) -> Cow<'a, str> {
+        match self {
+            Redaction::None => Cow::Borrowed(request_id),
+            Redaction::Hashed => Cow::Owned(
+                sha256(request_id.as_bytes())[..8]
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect(),
+            ),
+            Redaction::Shortened => {
+                let start = request_id
+                    .char_indices()
+                    .rev()
+                    .nth(7)
+                    .map_or(0, |(index, _)| index);
+
+                Cow::Owned(format!("...{}", &request_id[start..]))
+            }
+        }
+    }
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Computes the SHA-256 of the given bytes, per FIPS 180-4.
+///
+/// Request ids are short, so this plain implementation is enough, and avoids a dependency.
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(bytes.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 32];
+
+    for (chunk, value) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Redaction, redaction::sha256};
+
+    #[test]
+    fn test_redaction_sha256_known_vectors() {
+        assert_eq!(
+            sha256(b"abc")[..8],
+            [0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea]
+        );
+        assert_eq!(
+            sha256(b"")[..8],
+            [0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14]
+        );
+    }
+
+    #[test]
+    fn test_redaction_hashed_is_stable() {
+        assert_eq!(Redaction::Hashed.redact("abc"), "ba7816bf8f01cfea");
+    }
+}
@@ -0,0 +1,534 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::ACCEPT_LANGUAGE},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::{RequestIdConfig, RequestIdError, config::Callback};
+
+const HEADER_X_REQUEST_ID_ERROR: &str = "x-request-id-error";
+
+/// Renders a rejected request id into a response, e.g. as text, JSON, problem+json or HTML.
+///
+/// It's injected via `RequestIdConfig::with_rejection_renderer`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use axum::response::{Html, IntoResponse, Response};
+/// use request_id_middleware::{RejectionRenderer, RequestIdConfig, RequestIdError};
+///
+/// struct HtmlRenderer;
+///
+/// impl RejectionRenderer for HtmlRenderer {
+///     fn render(&self, error: &RequestIdError) -> Response {
+///         (error.status(), Html(format!("<p>{}</p>", error))).into_response()
+///     }
+/// }
+///
+/// let config = RequestIdConfig::default().with_rejection_renderer(Arc::new(HtmlRenderer));
+/// ```
+pub trait RejectionRenderer: Send + Sync {
+    /// Renders the given error into a response.
+    fn render(&self, error: &RequestIdError) -> Response;
+
+    /// Renders the given error into a response, with the message of the configured `MessageProvider`.
+    ///
+    /// By default, the message is ignored, and it's rendered via `render`.
+    fn render_message(&self, error: &RequestIdError, message: &str) -> Response {
+        let _ = message;
+
+        self.render(error)
+    }
+}
+
+/// Renders the rejection as a plain text body, e.g. `Invalid X-Request-Id : Not a valid UUID` (default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRenderer;
+
+impl RejectionRenderer for TextRenderer {
+    fn render(&self, error: &RequestIdError) -> Response {
+        self.render_message(error, &error.to_string())
+    }
+
+    fn render_message(&self, error: &RequestIdError, message: &str) -> Response {
+        (error.status(), message.to_string()).into_response()
+    }
+}
+
+/// Renders the rejection as a JSON body, e.g. `{"error":"Invalid X-Request-Id : Not a valid UUID"}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRenderer;
+
+impl RejectionRenderer for JsonRenderer {
+    fn render(&self, error: &RequestIdError) -> Response {
+        self.render_message(error, &error.to_string())
+    }
+
+    fn render_message(&self, error: &RequestIdError, message: &str) -> Response {
+        (error.status(), Json(json!({ "error": message }))).into_response()
+    }
+}
+
+/// The rejection of the request id extractors and layer.
+///
+/// It's rendered into a response by the configured `RejectionRenderer`, or by `TextRenderer` by default.
+/// Whatever the renderer, the response carries the `X-Request-Id-Error` header, set to the stable code of the reason,
+/// e.g. `not-a-v7`, so that gateways and clients can branch on it without parsing the body.
+/// To map it into the error type of an application instead, implement `From<RequestIdRejection>` for that type,
+/// and extract `Result<ExtractRequestId, RequestIdRejection>` in the handler.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{
+///     http::StatusCode,
+///     response::{IntoResponse, Response},
+///     routing::get,
+///     Router,
+/// };
+/// use request_id_middleware::{ExtractRequestId, RequestIdRejection};
+///
+/// enum AppError {
+///     BadRequest(&'static str),
+/// }
+///
+/// impl From<RequestIdRejection> for AppError {
+///     fn from(rejection: RequestIdRejection) -> Self {
+///         let (_, reason) = rejection.into_error_parts();
+///
+///         AppError::BadRequest(reason)
+///     }
+/// }
+///
+/// impl IntoResponse for AppError {
+///     fn into_response(self) -> Response {
+///         match self {
+///             AppError::BadRequest(reason) => (StatusCode::BAD_REQUEST, reason).into_response(),
+///         }
+///     }
+/// }
+///
+/// async fn handler(
+///     request_id: Result<ExtractRequestId, RequestIdRejection>,
+/// ) -> Result<String, AppError> {
+///     Ok(request_id?.into_inner())
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestIdRejection {
+    error: RequestIdError,
+    renderer: Option<Callback<dyn RejectionRenderer>>,
+    message: Option<String>,
+    status: Option<StatusCode>,
+}
+
+impl RequestIdRejection {
+    pub(crate) fn new(
+        error: RequestIdError,
+        renderer: Option<&Arc<dyn RejectionRenderer>>,
+    ) -> Self {
+        Self {
+            error,
+            renderer: renderer.map(|renderer| Callback(renderer.clone())),
+            message: None,
+            status: None,
+        }
+    }
+
+    /// Builds the rejection rendered per the given configuration, and calls its rejection hook.
+    ///
+    /// The message of the configured provider, if any, is resolved from the `Accept-Language` of the given request headers,
+    /// and the status from the configured mapping, if any.
+    pub(crate) fn with_config(
+        error: RequestIdError,
+        config: &RequestIdConfig,
+        headers: &HeaderMap,
+    ) -> Self {
+        if let Some(on_rejection) = config.on_rejection() {
+            on_rejection(&error);
+        }
+
+        let message = config
+            .message_provider()
+            .map(|message_provider| message_provider(&error, accept_language(headers)));
+
+        let status = config.status_for().map(|status_for| status_for(&error));
+
+        Self {
+            message,
+            status,
+            ..Self::new(error, config.rejection_renderer())
+        }
+    }
+
+    /// Overrides the HTTP status code of the response, whatever the configured mapping.
+    pub(crate) fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Returns the reason why the request id is rejected.
+    pub fn error(&self) -> &RequestIdError {
+        &self.error
+    }
+
+    /// Returns the message rendered in the response body, i.e. the one of the configured provider, or the default one.
+    pub fn message(&self) -> String {
+        match &self.message {
+            Some(message) => message.clone(),
+            None => self.error.to_string(),
+        }
+    }
+
+    /// Returns the HTTP status code of the response, i.e. the one of the configured mapping, or the default one.
+    pub fn status(&self) -> StatusCode {
+        self.status.unwrap_or_else(|| self.error.status())
+    }
+
+    /// Returns the HTTP status code and the reason, e.g. `Not a valid UUID`, ignoring the configured renderer.
+    pub fn into_error_parts(self) -> (StatusCode, &'static str) {
+        (self.status(), self.error.reason())
+    }
+}
+
+impl From<RequestIdError> for RequestIdRejection {
+    fn from(error: RequestIdError) -> Self {
+        Self::new(error, None)
+    }
+}
+
+impl PartialEq<(StatusCode, String)> for RequestIdRejection {
+    fn eq(&self, (status, message): &(StatusCode, String)) -> bool {
+        self.status() == *status && self.error.to_string() == *message
+    }
+}
+
+impl IntoResponse for RequestIdRejection {
+    fn into_response(self) -> Response {
+        let renderer: &dyn RejectionRenderer = match &self.renderer {
+            Some(renderer) => renderer.0.as_ref(),
+            None => &TextRenderer,
+        };
+
+        let mut response = match &self.message {
+            Some(message) => renderer.render_message(&self.error, message),
+            None => renderer.render(&self.error),
+        };
+
+        if let Some(status) = self.status {
+            *response.status_mut() = status;
+        }
+
+        response.headers_mut().insert(
+            HeaderName::from_static(HEADER_X_REQUEST_ID_ERROR),
+            HeaderValue::from_static(self.error.code()),
+        );
+
+        response
+    }
+}
+
+/// Returns the preferred language tag of the `Accept-Language` header, per RFC 9110 section 12.5.4,
+/// e.g. `fr-FR` for `fr-FR,fr;q=0.9,en;q=0.8`, or `fr` for `en;q=0.1, fr;q=0.9`.
+///
+/// The ranges are ordered by their weight, the first one winning ties, and the `*` wildcard and the ranges weighted 0 are skipped.
+fn accept_language(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(ACCEPT_LANGUAGE)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let tag = params.next()?.trim();
+
+            let weight = params
+                .find_map(|param| {
+                    let (key, value) = param.split_once('=')?;
+
+                    key.trim()
+                        .eq_ignore_ascii_case("q")
+                        .then(|| value.trim().parse::<f32>().unwrap_or(0.0))
+                })
+                .unwrap_or(1.0);
+
+            (!tag.is_empty() && tag != "*" && weight > 0.0).then_some((tag, weight))
+        })
+        .fold(
+            None,
+            |preferred: Option<(&str, f32)>, (tag, weight)| match preferred {
+                Some((_, preferred_weight)) if preferred_weight >= weight => preferred,
+                _ => Some((tag, weight)),
+            },
+        )
+        .map(|(tag, _)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        JsonRenderer, RejectionRenderer, RequestIdConfig, RequestIdError, RequestIdLayer,
+        RequestIdRejection, rejection::accept_language,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, HeaderValue, Request, StatusCode},
+        response::{Html, IntoResponse, Response},
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    struct HtmlRenderer;
+
+    impl RejectionRenderer for HtmlRenderer {
+        fn render(&self, error: &RequestIdError) -> Response {
+            (error.status(), Html(format!("<p>{}</p>", error))).into_response()
+        }
+    }
+
+    async fn reject(renderer: Arc<dyn RejectionRenderer>) -> (StatusCode, String) {
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_rejection_renderer(renderer),
+            ));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "this-is-not-a-uuid")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn test_rejection_status_for() {
+        let config = RequestIdConfig::default().with_status_for(|error| match error {
+            RequestIdError::Missing => StatusCode::PRECONDITION_REQUIRED,
+            RequestIdError::NotV7 => StatusCode::UNPROCESSABLE_ENTITY,
+            RequestIdError::ReservedPrefix => StatusCode::FORBIDDEN,
+            error => error.status(),
+        });
+
+        for (error, status) in [
+            (RequestIdError::Missing, StatusCode::PRECONDITION_REQUIRED),
+            (RequestIdError::NotV7, StatusCode::UNPROCESSABLE_ENTITY),
+            (RequestIdError::ReservedPrefix, StatusCode::FORBIDDEN),
+            (RequestIdError::NotUuid, StatusCode::BAD_REQUEST),
+            (RequestIdError::AlreadySeen, StatusCode::CONFLICT),
+        ] {
+            let rejection = RequestIdRejection::with_config(error, &config, &HeaderMap::new());
+
+            assert_eq!(rejection.status(), status);
+            assert_eq!(rejection.into_response().status(), status);
+        }
+
+        let rejection = RequestIdRejection::with_config(
+            RequestIdError::NotV7,
+            &RequestIdConfig::default().with_rejection_renderer(Arc::new(JsonRenderer)),
+            &HeaderMap::new(),
+        );
+
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            RequestIdRejection::with_config(
+                RequestIdError::NotV7,
+                &config.with_rejection_renderer(Arc::new(JsonRenderer)),
+                &HeaderMap::new(),
+            )
+            .into_response()
+            .status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_rejection_error_header() {
+        for (error, code) in [
+            (RequestIdError::NotAscii, "not-ascii"),
+            (RequestIdError::ControlCharacter, "control-character"),
+            (RequestIdError::NotUuid, "not-a-uuid"),
+            (RequestIdError::NotV7, "not-a-v7"),
+            (RequestIdError::ZeroTimestamp, "zero-timestamp"),
+            (RequestIdError::ClockSkew, "clock-skew"),
+            (RequestIdError::Missing, "missing"),
+            (RequestIdError::Mismatch, "mismatch"),
+            (RequestIdError::AlreadySeen, "already-seen"),
+            (RequestIdError::FetchFailed, "fetch-failed"),
+            (RequestIdError::ReservedPrefix, "reserved-prefix"),
+            (RequestIdError::UnknownVersion, "unknown-version"),
+            (RequestIdError::NotUlid, "not-a-ulid"),
+            (RequestIdError::TooLong, "too-long"),
+            (RequestIdError::InvalidVariant, "invalid-variant"),
+            (RequestIdError::Denied, "denied"),
+            (RequestIdError::ZeroRandom, "zero-random"),
+            (RequestIdError::Expired, "expired"),
+            (RequestIdError::InFlight, "in-flight"),
+        ] {
+            let response = RequestIdRejection::from(error.clone()).into_response();
+
+            assert_eq!(response.status(), error.status());
+            assert_eq!(response.headers()["x-request-id-error"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejection_custom_html_renderer() {
+        assert_eq!(
+            reject(Arc::new(HtmlRenderer)).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "<p>Invalid X-Request-Id : Not a valid UUID</p>".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_json_renderer() {
+        assert_eq!(
+            reject(Arc::new(JsonRenderer)).await,
+            (
+                StatusCode::BAD_REQUEST,
+                r#"{"error":"Invalid X-Request-Id : Not a valid UUID"}"#.to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_hook_labels() {
+        let rejections = Arc::new(Mutex::new(HashMap::<&'static str, usize>::new()));
+
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(RequestIdLayer::new({
+                let rejections = rejections.clone();
+
+                RequestIdConfig::default().with_on_rejection(move |error| {
+                    *rejections.lock().unwrap().entry(error.code()).or_default() += 1;
+                })
+            }));
+
+        for request_id in [
+            &b"this-is-not-a-uuid"[..],
+            b"6edaba95-4f5b-4547-be3f-85210d3ff8bf",
+            b"01965864-f8ab-7eb8-912a-a2c999ab110\xe9",
+            b"foo",
+            b"01965864-f8ab-7eb8-912a-a2c999ab110e",
+        ] {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/")
+                        .header("X-Request-Id", HeaderValue::from_bytes(request_id).unwrap())
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            *rejections.lock().unwrap(),
+            HashMap::from([("not-a-uuid", 2), ("not-a-v7", 1), ("not-ascii", 1)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejection_localized_message() {
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(RequestIdLayer::new(
+                RequestIdConfig::default().with_message_provider(
+                    |error, language| match language {
+                        Some(language) if language.starts_with("fr") => {
+                            format!("X-Request-Id invalide : {}", error.code())
+                        }
+                        _ => error.to_string(),
+                    },
+                ),
+            ));
+
+        let body = |response: Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "this-is-not-a-uuid")
+                    .header("Accept-Language", "fr-FR,fr;q=0.9,en;q=0.8")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body(response).await, "X-Request-Id invalide : not-a-uuid");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("X-Request-Id", "this-is-not-a-uuid")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            body(response).await,
+            "Invalid X-Request-Id : Not a valid UUID"
+        );
+    }
+
+    #[test]
+    fn test_rejection_accept_language() {
+        let preferred = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("accept-language", HeaderValue::from_str(value).unwrap());
+
+            accept_language(&headers).map(str::to_string)
+        };
+
+        assert_eq!(
+            preferred("fr-FR,fr;q=0.9,en;q=0.8").as_deref(),
+            Some("fr-FR")
+        );
+        assert_eq!(preferred("*;q=0.5, fr").as_deref(), Some("fr"));
+        assert_eq!(preferred("en;q=0.1, fr;q=0.9").as_deref(), Some("fr"));
+        assert_eq!(preferred("en;q=0.5, fr;q=0.5").as_deref(), Some("en"));
+        assert_eq!(preferred("en;q=0, *").as_deref(), None);
+        assert_eq!(preferred("de; Q=1.0 ,fr").as_deref(), Some("de"));
+        assert_eq!(accept_language(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,2305 @@
+use std::{
+    borrow::Cow,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use axum::http::{
+    Extensions, HeaderMap, HeaderName, HeaderValue, Request, header::USER_AGENT, request::Parts,
+};
+use log::{debug, error, warn};
+use percent_encoding::percent_decode_str;
+use uuid::{Uuid, Variant, Version};
+
+use crate::{
+    CanonicalForm, Enforcement, IdSource, LetterCase, LogField, RequestId, RequestIdConfig,
+    RequestIdError, SourceSpec, TrustLevel,
+    format::format,
+    forwarded::{immediate_upstream, is_trusted},
+    generate::generate,
+    layer::write_echo,
+    source::Found,
+};
+
+/// The length of a canonical, i.e. hyphenated, UUID, e.g. `01965864-f8ab-7eb8-912a-a2c999ab110e`.
+pub const CANONICAL_UUID_LEN: usize = 36;
+
+/// The lengths of the accepted UUID forms : simple, hyphenated, braced and URN.
+const ACCEPTED_UUID_LENS: [usize; 4] = [32, CANONICAL_UUID_LEN, 38, 45];
+
+/// The trailers of a request, inserted into its extensions by a layer that has access to them.
+///
+/// They're read as a fallback source when `RequestIdConfig::with_read_trailers` is enabled.
+/// Since trailers come after the body, it's up to that layer to buffer the body before inserting them.
+#[derive(Debug, Clone, Default)]
+pub struct RequestTrailers(pub HeaderMap);
+
+/// The request id resolved for a request, inserted into its extensions by the first layer or extractor that resolves it.
+///
+/// It's the canonical extension of this crate : all its layers and extractors read it before resolving the request id,
+/// so that they all see the same value, e.g. when several `RequestIdLayer` are chained, one per concern.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{Extension, routing::get, Router};
+/// use request_id_middleware::{RequestIdConfig, RequestIdLayer, ResolvedRequestId};
+///
+/// async fn handler(Extension(resolved): Extension<ResolvedRequestId>) -> String {
+///     format!("{} (generated : {})", resolved.id, resolved.generated)
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(RequestIdLayer::new(RequestIdConfig::default()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRequestId {
+    /// The request id.
+    pub id: String,
+    /// Whether the request id was generated, rather than sent by the client.
+    pub generated: bool,
+    /// The header of the configuration that resolved the request id.
+    pub header_name: HeaderName,
+}
+
+impl ResolvedRequestId {
+    /// Returns the key and value of the request id, e.g. `("request_id", "01965864-f8ab-7eb8-912a-a2c999ab110e")`,
+    /// for inclusion in the access-log lines, see `RequestIdLayer::around`.
+    pub fn access_log_field(&self) -> (&str, &str) {
+        ("request_id", &self.id)
+    }
+}
+
+/// Returns the request id resolved for a request, from its extensions, e.g. in a middleware that isn't an extractor.
+///
+/// It's stored as a `ResolvedRequestId`, a type owned by this crate, so it never collides with a `String` or any other
+/// extension inserted by another crate.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::Extensions;
+/// use request_id_middleware::request_id_from_extensions;
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert("foo".to_string());
+///
+/// assert_eq!(request_id_from_extensions(&extensions), None);
+/// ```
+pub fn request_id_from_extensions(extensions: &Extensions) -> Option<&str> {
+    extensions
+        .get::<ResolvedRequestId>()
+        .map(|resolved| resolved.id.as_str())
+}
+
+/// Resolves the request id from the configured sources, in order, or generates a new one if none carries a value.
+/// Returns the request id and whether it was generated, or the reason why it's rejected.
+pub(crate) fn resolve(
+    parts: &Parts,
+    config: &RequestIdConfig,
+) -> Result<(String, bool), RequestIdError> {
+    resolve_from(parts, config, &mut None)
+}
+
+/// Resolves the request id like `resolve`, and sets the source that carried a value, if any.
+pub(crate) fn resolve_from(
+    parts: &Parts,
+    config: &RequestIdConfig,
+    matched: &mut Option<IdSource>,
+) -> Result<(String, bool), RequestIdError> {
+    match resolve_sources(parts, config, matched) {
+        Err(err) if config.enforcement() == Enforcement::WarnOnly => {
+            warn!(
+                "[{}] Accepted invalid request id in warn-only mode due to : {:?}",
+                config.header_name(),
+                err
+            );
+
+            Ok((generate(config), true))
+        }
+        Err(err) => {
+            if !config.log_fields().is_empty() {
+                error!(
+                    "[{}] Rejected request id due to : {} ({})",
+                    config.header_name(),
+                    err.reason(),
+                    log_fields(parts, config.log_fields())
+                );
+            }
+
+            Err(err)
+        }
+        resolved => resolved,
+    }
+}
+
+/// Formats the given request fields for a log line, e.g. `method=POST path=/orders`.
+fn log_fields(parts: &Parts, fields: &[LogField]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| match field {
+            LogField::Method => format!("method={}", parts.method),
+            LogField::Path => format!("path={}", parts.uri.path()),
+            LogField::ClientIp => match immediate_upstream(parts) {
+                Some(ip) => format!("client_ip={}", ip),
+                None => "client_ip=-".to_string(),
+            },
+            LogField::UserAgent => match parts.headers.get(USER_AGENT) {
+                Some(user_agent) => format!("user_agent={:?}", user_agent),
+                None => "user_agent=-".to_string(),
+            },
+        })
+        .collect();
+
+    fields.join(" ")
+}
+
+fn resolve_sources(
+    parts: &Parts,
+    config: &RequestIdConfig,
+    matched: &mut Option<IdSource>,
+) -> Result<(String, bool), RequestIdError> {
+    let default_sources;
+    let sources = match config.sources() {
+        Some(sources) => sources,
+        None => {
+            default_sources = default_sources_of(config)
+                .into_iter()
+                .map(SourceSpec::from)
+                .collect::<Vec<_>>();
+            default_sources.as_slice()
+        }
+    };
+
+    let ulid = negotiates_ulid(parts, config)?;
+    let trusted = is_trusted(parts, config);
+
+    for spec in sources.iter().filter(|_| trusted) {
+        let source = spec.source();
+
+        if let Some(found) = source.find(parts) {
+            if config.nil_means_generate()
+                && let Ok(Found::Sent(request_id)) = &found
+                && Uuid::try_parse(strip_id_prefix(request_id.trim(), config))
+                    .is_ok_and(|uuid| uuid.is_nil())
+            {
+                debug!(
+                    "[{}] Ignoring nil request id from {}",
+                    config.header_name(),
+                    source
+                );
+
+                continue;
+            }
+
+            *matched = Some(source.clone());
+
+            let request_id = match found? {
+                Found::Sent(sent) => {
+                    let request_id = if !spec.validate() {
+                        pass_through(&sent, config)?
+                    } else {
+                        validate_negotiated(&sent, ulid, config)?
+                    };
+
+                    if spec.normalize() {
+                        request_id
+                    } else {
+                        strip_id_prefix(sent.trim(), config).to_string()
+                    }
+                }
+                Found::Trusted(uuid) => format(
+                    None,
+                    &uuid,
+                    config.canonical_form(),
+                    config.normalize_case(),
+                ),
+                Found::PassThrough(request_id) => pass_through(&request_id, config)?,
+            };
+
+            debug!(
+                "[{}] Resolved request id : {}",
+                config.header_name(),
+                config.log_redaction().redact(&request_id)
+            );
+
+            return Ok((request_id, false));
+        }
+    }
+
+    if config
+        .require_id()
+        .is_some_and(|require_id| require_id(parts))
+    {
+        if let Some(fallback_id) = config.fallback_id() {
+            warn!(
+                "[{}] Using the fallback request id due to : Missing",
+                config.header_name()
+            );
+
+            return Ok((fallback_id.to_string(), false));
+        }
+
+        error!(
+            "[{}] Failed to resolve request id due to : Missing",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::Missing);
+    }
+
+    let request_id = generate(config);
+
+    debug!(
+        "[{}] Generated request id : {}",
+        config.header_name(),
+        config.log_redaction().redact(&request_id)
+    );
+
+    Ok((request_id, true))
+}
+
+/// Returns the default chain, i.e. the configured header, then the request trailers if enabled.
+pub(crate) fn default_sources_of(config: &RequestIdConfig) -> Vec<IdSource> {
+    let mut sources = vec![IdSource::Header(config.header_name().clone())];
+
+    if config.read_trailers() {
+        sources.push(IdSource::Trailer(config.header_name().clone()));
+    }
+
+    sources
+}
+
+/// Validates the given request id, or generates a new UUID v7 if it's missing, outside of any HTTP framework.
+///
+/// Returns the request id, formatted per the configured canonical form and letter case, and whether it was generated.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestIdConfig, RequestIdError, validate_or_generate};
+///
+/// let config = RequestIdConfig::default();
+///
+/// assert_eq!(
+///     validate_or_generate(Some("01965864-f8ab-7eb8-912a-a2c999ab110e"), &config),
+///     Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+/// );
+/// assert_eq!(validate_or_generate(Some("foo"), &config), Err(RequestIdError::NotUuid));
+/// assert!(validate_or_generate(None, &config).unwrap().1);
+/// ```
+pub fn validate_or_generate(
+    raw: Option<&str>,
+    config: &RequestIdConfig,
+) -> Result<(String, bool), RequestIdError> {
+    match raw {
+        Some(raw) => validate(raw, config).map(|request_id| (request_id, false)),
+        None => Ok((generate(config), true)),
+    }
+}
+
+/// A deviation of a valid request id from the canonical form, i.e. an hyphenated lowercase UUID, see `validate_with_warnings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationWarning {
+    /// The request id had leading or trailing whitespace.
+    Whitespace,
+    /// The request id had uppercase hexadecimal digits.
+    Uppercase,
+    /// The request id was in the simple form, without hyphens.
+    MissingHyphens,
+    /// The request id was braced, e.g. `{UUID}`.
+    Braced,
+    /// The request id was an URN, e.g. `urn:uuid:UUID`.
+    Urn,
+    /// The request id was percent-encoded.
+    PercentEncoded,
+}
+
+/// Validates a request id exactly like the extractor does, and returns its deviations from the canonical form along with it.
+///
+/// The request id is formatted per the configured canonical form and letter case, like `validate_or_generate` does.
+/// It lets operators collect how clients deviate from the canonical form during a migration, without rejecting them.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{CanonicalForm, RequestIdConfig, ValidationWarning, validate_with_warnings};
+///
+/// let config = RequestIdConfig::default().with_canonical_form(CanonicalForm::Hyphenated);
+///
+/// assert_eq!(
+///     validate_with_warnings(" 01965864F8AB7EB8912AA2C999AB110E", &config),
+///     Ok((
+///         "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+///         vec![
+///             ValidationWarning::Whitespace,
+///             ValidationWarning::Uppercase,
+///             ValidationWarning::MissingHyphens
+///         ]
+///     ))
+/// );
+/// ```
+pub fn validate_with_warnings(
+    raw: &str,
+    config: &RequestIdConfig,
+) -> Result<(String, Vec<ValidationWarning>), RequestIdError> {
+    let request_id = validate(raw, config)?;
+
+    let mut warnings = Vec::new();
+    let mut trimmed = raw.trim();
+
+    if trimmed.len() != raw.len() {
+        warnings.push(ValidationWarning::Whitespace);
+    }
+
+    let decoded;
+    if config.percent_decode() && trimmed.contains('%') {
+        warnings.push(ValidationWarning::PercentEncoded);
+
+        decoded = percent_decode_str(trimmed).decode_utf8_lossy();
+        trimmed = decoded.trim();
+    }
+
+    if trimmed.bytes().any(|byte| byte.is_ascii_uppercase()) {
+        warnings.push(ValidationWarning::Uppercase);
+    }
+
+    match trimmed.len() {
+        32 => warnings.push(ValidationWarning::MissingHyphens),
+        38 => warnings.push(ValidationWarning::Braced),
+        45 => warnings.push(ValidationWarning::Urn),
+        _ => {}
+    }
+
+    Ok((request_id, warnings))
+}
+
+/// Resolves the request id of the given headers in place, e.g. in a proxy before forwarding the request.
+///
+/// A valid request id is kept, formatted per the configured canonical form and letter case,
+/// and a missing one is generated, then both are written to the configured header.
+/// An invalid request id is left untouched, and the reason is returned.
+///
+/// The forwarded request id has no `RequestIdConfig::with_id_prefix`, like the request header rewritten by the layer,
+/// since the prefix only marks the request ids sent back to the clients.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::HeaderMap;
+/// use request_id_middleware::{RequestIdConfig, ensure_request_id};
+///
+/// let mut headers = HeaderMap::new();
+///
+/// let request_id = ensure_request_id(&mut headers, &RequestIdConfig::default()).unwrap();
+///
+/// assert_eq!(headers["x-request-id"], request_id);
+/// ```
+pub fn ensure_request_id(
+    headers: &mut HeaderMap,
+    config: &RequestIdConfig,
+) -> Result<String, RequestIdError> {
+    let (mut parts, _) = Request::new(()).into_parts();
+    parts.headers = std::mem::take(headers);
+
+    let resolved = resolve(&parts, config);
+    *headers = parts.headers;
+
+    let (request_id, _) = resolved?;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        headers.insert(config.header_name().clone(), value);
+    }
+
+    Ok(request_id)
+}
+
+/// Resolves the request id of the given request headers, and echoes it to the given response headers, in one call,
+/// e.g. in an adapter for a framework other than Axum.
+///
+/// The request id is resolved like the layer does, then echoed per `RequestIdConfig::with_echo_response`,
+/// `RequestIdConfig::with_response_write_mode` and `RequestIdConfig::with_id_prefix`. An invalid request id isn't echoed,
+/// and a request id that isn't an UUID, e.g. per `RequestIdConfig::with_generation_template`, is rejected as `RequestIdError::NotUuid`.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::HeaderMap;
+/// use request_id_middleware::{RequestIdConfig, extract_and_echo};
+///
+/// let mut request_headers = HeaderMap::new();
+/// request_headers.insert("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e".parse().unwrap());
+///
+/// let mut response_headers = HeaderMap::new();
+///
+/// let request_id =
+///     extract_and_echo(&request_headers, &mut response_headers, &RequestIdConfig::default()).unwrap();
+///
+/// assert_eq!(response_headers["x-request-id"], request_id.to_string());
+/// ```
+pub fn extract_and_echo(
+    request_headers: &HeaderMap,
+    response_headers: &mut HeaderMap,
+    config: &RequestIdConfig,
+) -> Result<RequestId, RequestIdError> {
+    let (mut parts, _) = Request::new(()).into_parts();
+    parts.headers = request_headers.clone();
+
+    let (request_id, _) = resolve(&parts, config)?;
+
+    let uuid = Uuid::try_parse(&request_id).map_err(|_| RequestIdError::NotUuid)?;
+
+    if config.echo_response()
+        && let Ok(value) = HeaderValue::from_str(&egress_id(&request_id, config))
+    {
+        write_echo(response_headers, config, value);
+    }
+
+    Ok(RequestId::from(uuid))
+}
+
+/// Validates a request id sent by the client, and formats it per the configured canonical form and letter case.
+pub(crate) fn validate(
+    request_id: &str,
+    config: &RequestIdConfig,
+) -> Result<String, RequestIdError> {
+    validate_negotiated(request_id, false, config)
+}
+
+/// Validates a request id sent by the client like `validate`, or as a ULID if it's the negotiated format.
+///
+/// The checks shared by both formats, i.e. the length, prefixes and control characters, run before branching on it,
+/// since the client chooses the format.
+fn validate_negotiated(
+    request_id: &str,
+    ulid: bool,
+    config: &RequestIdConfig,
+) -> Result<String, RequestIdError> {
+    check_len(request_id, config)?;
+
+    let decoded;
+    let request_id = if config.percent_decode() {
+        decoded = match percent_decode_str(request_id.trim()).decode_utf8() {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                error!(
+                    "[{}] Failed to percent-decode header due to : {:?}",
+                    config.header_name(),
+                    err
+                );
+
+                return Err(RequestIdError::NotAscii);
+            }
+        };
+
+        decoded.trim()
+    } else {
+        request_id.trim()
+    };
+
+    let request_id = strip_id_prefix(request_id, config);
+
+    if config.trust_level() == TrustLevel::Internal && !ulid {
+        if request_id.is_empty() {
+            error!(
+                "[{}] Failed to validate request id due to : Empty",
+                config.header_name()
+            );
+
+            return Err(RequestIdError::NotUuid);
+        }
+
+        return pass_through(request_id, config);
+    }
+
+    // Tabs are valid in header values, so they're rejected here, with a clearer reason than the UUID parser's.
+    if request_id.bytes().any(|byte| byte.is_ascii_control()) {
+        error!(
+            "[{}] Failed to validate request id due to : Control characters",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::ControlCharacter);
+    }
+
+    if let Some(prefix) = config
+        .reserved_prefixes()
+        .iter()
+        .find(|prefix| starts_with(request_id, prefix, config.prefix_case_sensitive()))
+    {
+        error!(
+            "[{}] Failed to validate request id due to : Reserved prefix {}",
+            config.header_name(),
+            prefix
+        );
+
+        return Err(RequestIdError::ReservedPrefix);
+    }
+
+    if ulid {
+        return validate_ulid(request_id, config);
+    }
+
+    let Some(template) = config.generation_template() else {
+        return validate_uuid(request_id, config);
+    };
+
+    let Some(uuid) = template.find_uuid(request_id) else {
+        error!(
+            "[{}] Failed to validate request id due to : Not matching the template",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::NotUuid);
+    };
+
+    Ok(format!(
+        "{}{}{}",
+        &request_id[..uuid.start],
+        validate_uuid(&request_id[uuid.clone()], config)?,
+        &request_id[uuid.end..]
+    ))
+}
+
+/// Validates an UUID sent by the client, and formats it per the configured canonical form and letter case.
+fn validate_uuid(request_id: &str, config: &RequestIdConfig) -> Result<String, RequestIdError> {
+    // Obviously wrong inputs are rejected before the parser does any work.
+    if !ACCEPTED_UUID_LENS.contains(&request_id.len()) {
+        error!(
+            "[{}] Failed to parse UUID due to : Length is {}",
+            config.header_name(),
+            request_id.len()
+        );
+
+        return Err(RequestIdError::NotUuid);
+    }
+
+    if config.require_exact_hyphenation() && !is_exactly_hyphenated(request_id) {
+        error!(
+            "[{}] Failed to parse UUID due to : Not exactly hyphenated",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::NotUuid);
+    }
+
+    let parsed_request_id = match Uuid::try_parse(request_id) {
+        Ok(parsed_request_id) => parsed_request_id,
+        Err(err) => {
+            error!(
+                "[{}] Failed to parse UUID due to : {:?}",
+                config.header_name(),
+                err
+            );
+
+            return Err(RequestIdError::NotUuid);
+        }
+    };
+
+    let request_id_version = parsed_request_id.get_version();
+
+    if !config.any_version()
+        && !request_id_version.is_some_and(|version| config.allowed_versions().contains(&version))
+    {
+        error!(
+            "[{}] Failed to validate UUID due to : Version is {:?}",
+            config.header_name(),
+            request_id_version
+        );
+
+        return Err(RequestIdError::NotV7);
+    }
+
+    if config.strict_variant()
+        && (1..=8).contains(&parsed_request_id.get_version_num())
+        && parsed_request_id.get_variant() != Variant::RFC4122
+    {
+        error!(
+            "[{}] Failed to validate UUID due to : Variant is {:?}",
+            config.header_name(),
+            parsed_request_id.get_variant()
+        );
+
+        return Err(RequestIdError::InvalidVariant);
+    }
+
+    check_denied(&parsed_request_id, config)?;
+
+    if config.reject_zero_timestamp() && parsed_request_id.as_bytes()[..6] == [0; 6] {
+        error!(
+            "[{}] Failed to validate UUID due to : Timestamp is zero",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::ZeroTimestamp);
+    }
+
+    if config.reject_zero_random()
+        && request_id_version == Some(Version::SortRand)
+        && is_zero_random(&parsed_request_id)
+    {
+        error!(
+            "[{}] Failed to validate UUID due to : Random bits are zero",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::ZeroRandom);
+    }
+
+    if let Some(on_clock_skew) = config.on_clock_skew()
+        && request_id_version == Some(Version::SortRand)
+        && let Some(timestamp) = parsed_request_id.get_timestamp()
+    {
+        let (seconds, nanos) = timestamp.to_unix();
+        let generated_at = UNIX_EPOCH + Duration::new(seconds, nanos);
+
+        on_clock_skew(match config.clock().now().duration_since(generated_at) {
+            Ok(skew) => skew.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        });
+    }
+
+    if let Some(max_clock_skew) = config.max_clock_skew()
+        && let Some(timestamp) = parsed_request_id.get_timestamp()
+    {
+        let (seconds, nanos) = timestamp.to_unix();
+        let generated_at = UNIX_EPOCH + Duration::new(seconds, nanos);
+
+        // A skew too large to be added to the current time is no limit.
+        if config
+            .clock()
+            .now()
+            .checked_add(max_clock_skew)
+            .is_some_and(|latest| generated_at > latest)
+        {
+            error!(
+                "[{}] Failed to validate UUID due to : Timestamp in the future",
+                config.header_name()
+            );
+
+            return Err(RequestIdError::ClockSkew);
+        }
+    }
+
+    if let Some(max_id_age) = config.max_id_age()
+        && let Some(timestamp) = parsed_request_id.get_timestamp()
+    {
+        let (seconds, nanos) = timestamp.to_unix();
+        let generated_at = UNIX_EPOCH + Duration::new(seconds, nanos);
+
+        // An age too long to be added to the timestamp never expires.
+        if generated_at
+            .checked_add(max_id_age)
+            .is_some_and(|expires_at| expires_at < config.clock().now())
+        {
+            error!(
+                "[{}] Failed to validate UUID due to : Timestamp too old",
+                config.header_name()
+            );
+
+            return Err(RequestIdError::Expired);
+        }
+    }
+
+    Ok(format(
+        Some(request_id),
+        &parsed_request_id,
+        config.canonical_form(),
+        config.normalize_case(),
+    ))
+}
+
+/// Returns whether the random bits of an UUID v7 are all zero, i.e. `rand_a` and `rand_b`, around the version and variant bits.
+fn is_zero_random(uuid: &Uuid) -> bool {
+    let bytes = uuid.as_bytes();
+
+    bytes[6] & 0x0f == 0 && bytes[7] == 0 && bytes[8] & 0x3f == 0 && bytes[9..] == [0; 7]
+}
+
+/// Returns whether the value has hyphens at exactly the 8, 13, 18 and 23 positions, and nowhere else, within 36 characters.
+fn is_exactly_hyphenated(request_id: &str) -> bool {
+    request_id.len() == CANONICAL_UUID_LEN
+        && request_id
+            .bytes()
+            .enumerate()
+            .all(|(index, byte)| (byte == b'-') == matches!(index, 8 | 13 | 18 | 23))
+}
+
+/// Adds the configured id prefix to the request id, as it's written to every response header echoing it.
+pub(crate) fn egress_id<'a>(request_id: &'a str, config: &RequestIdConfig) -> Cow<'a, str> {
+    match config.id_prefix() {
+        Some(prefix) => Cow::Owned(format!("{}{}", prefix, request_id)),
+        None => Cow::Borrowed(request_id),
+    }
+}
+
+/// Strips the configured id prefix from the value, if it starts with it.
+fn strip_id_prefix<'a>(request_id: &'a str, config: &RequestIdConfig) -> &'a str {
+    match config.id_prefix() {
+        Some(prefix) if starts_with(request_id, prefix, config.prefix_case_sensitive()) => {
+            &request_id[prefix.len()..]
+        }
+        _ => request_id,
+    }
+}
+
+/// Returns whether the value starts with the prefix, case-sensitively or not, as applied by all the prefix-based checks.
+fn starts_with(value: &str, prefix: &str, case_sensitive: bool) -> bool {
+    match value.get(..prefix.len()) {
+        Some(start) if case_sensitive => start == prefix,
+        Some(start) => start.eq_ignore_ascii_case(prefix),
+        None => false,
+    }
+}
+
+/// Returns whether the configured version header negotiates ULIDs rather than UUIDs v7, or rejects an unknown version.
+fn negotiates_ulid(parts: &Parts, config: &RequestIdConfig) -> Result<bool, RequestIdError> {
+    let Some(version) = config
+        .version_header()
+        .and_then(|name| parts.headers.get(name))
+    else {
+        return Ok(false);
+    };
+
+    match version.to_str().map(str::trim) {
+        Ok(version) if version.eq_ignore_ascii_case("v7") => Ok(false),
+        Ok(version) if version.eq_ignore_ascii_case("ulid") => Ok(true),
+        _ => {
+            error!(
+                "[{}] Failed to negotiate request id format due to : Unknown version {:?}",
+                config.header_name(),
+                version
+            );
+
+            Err(RequestIdError::UnknownVersion)
+        }
+    }
+}
+
+/// The digits of the Crockford base32 encoding of the ULIDs, i.e. without `I`, `L`, `O` and `U`.
+const CROCKFORD_BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Rejects an UUID per the configured denylist or allowlist.
+fn check_denied(uuid: &Uuid, config: &RequestIdConfig) -> Result<(), RequestIdError> {
+    if config
+        .denied_ids()
+        .is_some_and(|denied_ids| denied_ids.contains(uuid))
+        || config
+            .allowed_ids()
+            .is_some_and(|allowed_ids| !allowed_ids.contains(uuid))
+    {
+        error!(
+            "[{}] Failed to validate UUID due to : Denied",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::Denied);
+    }
+
+    Ok(())
+}
+
+/// Validates a ULID, i.e. 26 Crockford base32 characters not overflowing 128 bits, and uppercases it.
+///
+/// The denylist and allowlist apply to the 128 bits it encodes, i.e. the UUID with the same bytes.
+fn validate_ulid(request_id: &str, config: &RequestIdConfig) -> Result<String, RequestIdError> {
+    let bits = (request_id.len() == 26 && request_id.as_bytes()[0] <= b'7')
+        .then(|| {
+            request_id.bytes().try_fold(0u128, |bits, byte| {
+                let digit = CROCKFORD_BASE32
+                    .iter()
+                    .position(|&digit| digit == byte.to_ascii_uppercase())?;
+
+                Some(bits << 5 | digit as u128)
+            })
+        })
+        .flatten();
+
+    let Some(bits) = bits else {
+        error!(
+            "[{}] Failed to validate request id due to : Not a ULID",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::NotUlid);
+    };
+
+    check_denied(&Uuid::from_u128(bits), config)?;
+
+    Ok(request_id.to_ascii_uppercase())
+}
+
+/// Rejects a request id longer than the configured maximum length, before any allocation.
+fn check_len(request_id: &str, config: &RequestIdConfig) -> Result<(), RequestIdError> {
+    match config.max_len() {
+        Some(max_len) if request_id.len() > max_len => {
+            error!(
+                "[{}] Failed to validate request id due to : Length is {}",
+                config.header_name(),
+                request_id.len()
+            );
+
+            Err(RequestIdError::TooLong)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Normalizes a request id that isn't an UUID, i.e. trims it and applies the configured letter case.
+fn pass_through(request_id: &str, config: &RequestIdConfig) -> Result<String, RequestIdError> {
+    check_len(request_id, config)?;
+
+    let request_id = request_id.trim();
+
+    if request_id.bytes().any(|byte| byte.is_ascii_control()) {
+        error!(
+            "[{}] Failed to validate request id due to : Control characters",
+            config.header_name()
+        );
+
+        return Err(RequestIdError::ControlCharacter);
+    }
+
+    Ok(match config.normalize_case() {
+        LetterCase::Lower => request_id.to_ascii_lowercase(),
+        LetterCase::Upper => request_id.to_ascii_uppercase(),
+        LetterCase::Preserve => request_id.to_string(),
+    })
+}
+
+/// Validates and canonicalizes a batch of request ids, e.g. read from log lines, outside of any HTTP request.
+///
+/// Each line is trimmed, validated as an UUID v7 and formatted as an hyphenated lowercase UUID,
+/// whatever its form (braced, URN, simple or hyphenated) and case.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestIdError, canonicalize_batch};
+///
+/// let canonicalized = canonicalize_batch(&["{01965864-F8AB-7EB8-912A-A2C999AB110E}", "foo"]);
+///
+/// assert_eq!(
+///     canonicalized,
+///     vec![
+///         Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()),
+///         Err(RequestIdError::NotUuid)
+///     ]
+/// );
+/// ```
+pub fn canonicalize_batch(lines: &[&str]) -> Vec<Result<String, RequestIdError>> {
+    let config = RequestIdConfig::default().with_canonical_form(CanonicalForm::Hyphenated);
+
+    lines.iter().map(|line| validate(line, &config)).collect()
+}
+
+/// Marks the request id generated for a request, to check that a single one is generated per request.
+#[cfg(debug_assertions)]
+#[derive(Debug, Clone)]
+struct GeneratedRequestId(String);
+
+/// Marks, in debug builds only, the request id generated for a request, see `debug_assert_single_generation`.
+pub(crate) fn mark_generated(extensions: &mut Extensions, request_id: &str) {
+    #[cfg(debug_assertions)]
+    extensions.insert(GeneratedRequestId(request_id.to_string()));
+
+    #[cfg(not(debug_assertions))]
+    let _ = (extensions, request_id);
+}
+
+/// Asserts, in debug builds only, that a generated request id reused from the extensions is the one generated for the request.
+///
+/// The layers and extractors generating a request id mark it, then share it as a `ResolvedRequestId` with the next ones,
+/// so a distinct one means that something in between, e.g. a middleware inserting its own `ResolvedRequestId`,
+/// generated a second request id for the same request. In release builds, it's a no-op.
+pub(crate) fn debug_assert_single_generation(
+    extensions: &Extensions,
+    resolved: &ResolvedRequestId,
+) {
+    #[cfg(debug_assertions)]
+    if resolved.generated
+        && let Some(GeneratedRequestId(generated)) = extensions.get::<GeneratedRequestId>()
+    {
+        assert_eq!(
+            generated, &resolved.id,
+            "Two distinct request ids were generated for the same request"
+        );
+    }
+
+    #[cfg(not(debug_assertions))]
+    let _ = (extensions, resolved);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use crate::{
+        CANONICAL_UUID_LEN, CanonicalForm, Enforcement, IdSource, IdTemplate, LetterCase,
+        RequestId, RequestIdConfig, RequestIdError, RequestTrailers, ResolvedRequestId, TrustLevel,
+        resolve::{
+            ValidationWarning, canonicalize_batch, ensure_request_id, extract_and_echo,
+            request_id_from_extensions, resolve, validate_or_generate, validate_with_warnings,
+        },
+    };
+    use axum::{
+        extract::ConnectInfo,
+        http::{
+            Extensions, HeaderMap, HeaderName, HeaderValue, Request, StatusCode, request::Parts,
+        },
+    };
+    use uuid::{Builder, Uuid, Variant, Version};
+
+    fn parts(headers: HeaderMap, extensions: Extensions) -> Parts {
+        let (mut parts, _) = Request::new(()).into_parts();
+
+        parts.headers = headers;
+        parts.extensions = extensions;
+        parts
+    }
+
+    fn headers(request_id: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("x-request-id", HeaderValue::from_str(request_id).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_resolve_not_ascii() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            "x-request-id",
+            HeaderValue::from_bytes("01965864-f8ab-7eb8-912a-a2c999ab110é".as_bytes()).unwrap(),
+        );
+
+        assert_eq!(
+            resolve(
+                &parts(headers, Extensions::new()),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::NotAscii)
+        );
+    }
+
+    #[test]
+    fn test_resolve_control_character() {
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8\t-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::ControlCharacter)
+        );
+    }
+
+    #[test]
+    fn test_resolve_allowed_versions() {
+        let request_id = "2ed6657d-e927-568b-95e1-2665a8aea6a2";
+
+        assert_eq!(
+            resolve(
+                &parts(headers(request_id), Extensions::new()),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::NotV7)
+        );
+        assert_eq!(
+            resolve(
+                &parts(headers(request_id), Extensions::new()),
+                &RequestIdConfig::default()
+                    .with_allowed_versions(vec![Version::SortRand, Version::Sha1])
+            ),
+            Ok((request_id.to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_reserved_prefixes_case() {
+        let config =
+            RequestIdConfig::default().with_reserved_prefixes(vec!["Internal-".to_string()]);
+        let request_id = "INTERNAL-01965864-f8ab-7eb8-912a-a2c999ab110e";
+
+        // Case-sensitively, the prefix doesn't match, so the request id is rejected as an invalid UUID instead.
+        assert_eq!(
+            resolve(&parts(headers(request_id), Extensions::new()), &config),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            resolve(
+                &parts(headers(request_id), Extensions::new()),
+                &config.with_prefix_case_sensitive(false)
+            ),
+            Err(RequestIdError::ReservedPrefix)
+        );
+    }
+
+    #[test]
+    fn test_resolve_require_exact_hyphenation() {
+        let config = RequestIdConfig::default().with_require_exact_hyphenation(true);
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+
+        for request_id in [
+            "01965864f8ab7eb8912aa2c999ab110e",
+            "{01965864-f8ab-7eb8-912a-a2c999ab110e}",
+            "urn:uuid:01965864-f8ab-7eb8-912a-a2c999ab110e",
+        ] {
+            assert!(
+                resolve(
+                    &parts(headers(request_id), Extensions::new()),
+                    &RequestIdConfig::default()
+                )
+                .is_ok()
+            );
+            assert_eq!(
+                resolve(&parts(headers(request_id), Extensions::new()), &config),
+                Err(RequestIdError::NotUuid)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_trust_level_internal() {
+        let config = RequestIdConfig::default().with_trust_level(TrustLevel::Internal);
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers(" 6EDABA95-4F5B-4547-BE3F-85210D3FF8BF "),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("6edaba95-4f5b-4547-be3f-85210d3ff8bf".to_string(), false))
+        );
+        assert_eq!(
+            resolve(&parts(headers(" "), Extensions::new()), &config),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("6edaba95-4f5b-4547-be3f-85210d3ff8bf"),
+                    Extensions::new()
+                ),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::NotV7)
+        );
+    }
+
+    #[test]
+    fn test_resolve_generation_template() {
+        let config =
+            RequestIdConfig::default().with_generation_template(IdTemplate::new("req_{uuid}"));
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("req_01965864-F8AB-7EB8-912A-A2C999AB110E"),
+                    Extensions::new()
+                ),
+                &config.clone().with_normalize_case(LetterCase::Lower)
+            ),
+            Ok((
+                "req_01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                false
+            ))
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("req_6edaba95-4f5b-4547-be3f-85210d3ff8bf"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Err(RequestIdError::NotV7)
+        );
+    }
+
+    #[test]
+    fn test_resolve_require_tls() {
+        let config = RequestIdConfig::default().with_require_tls(true);
+
+        for (name, value, sent) in [
+            ("x-forwarded-proto", "http", false),
+            ("x-forwarded-proto", "https, http", true),
+            (
+                "forwarded",
+                "for=192.0.2.60;proto=HTTPS;by=203.0.113.43",
+                true,
+            ),
+            ("forwarded", "for=192.0.2.60;proto=http", false),
+        ] {
+            let mut headers = headers("01965864-f8ab-7eb8-912a-a2c999ab110e");
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+
+            let (request_id, generated) =
+                resolve(&parts(headers, Extensions::new()), &config).unwrap();
+
+            assert_eq!(generated, !sent, "{}: {}", name, value);
+            assert_eq!(
+                request_id == "01965864-f8ab-7eb8-912a-a2c999ab110e",
+                sent,
+                "{}: {}",
+                name,
+                value
+            );
+        }
+
+        // Without any forwarded header, the scheme of an origin-form URI is unknown, so the request id is regenerated.
+        assert!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            )
+            .unwrap()
+            .1
+        );
+    }
+
+    #[test]
+    fn test_resolve_request_id_from_extensions() {
+        let mut extensions = Extensions::new();
+
+        extensions.insert(ResolvedRequestId {
+            id: "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+            generated: false,
+            header_name: HeaderName::from_static("x-request-id"),
+        });
+        extensions.insert("0196583c-4d2a-7087-9beb-6214d18ec924".to_string());
+
+        assert_eq!(
+            request_id_from_extensions(&extensions),
+            Some("01965864-f8ab-7eb8-912a-a2c999ab110e")
+        );
+        assert_eq!(
+            extensions.get::<String>().map(String::as_str),
+            Some("0196583c-4d2a-7087-9beb-6214d18ec924")
+        );
+    }
+
+    #[test]
+    fn test_resolve_trusted_proxies() {
+        let config = RequestIdConfig::default().with_trusted_proxies(vec![
+            "10.0.0.1".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+        ]);
+
+        for (name, value, sent) in [
+            ("x-forwarded-for", "198.51.100.7, 10.0.0.1", true),
+            ("x-forwarded-for", "10.0.0.1, 198.51.100.7", false),
+            (
+                "forwarded",
+                "for=198.51.100.7, for=\"[2001:db8::1]:4711\"",
+                true,
+            ),
+            ("forwarded", "for=198.51.100.7", false),
+        ] {
+            let mut headers = headers("01965864-f8ab-7eb8-912a-a2c999ab110e");
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+
+            let (_, generated) = resolve(&parts(headers, Extensions::new()), &config).unwrap();
+
+            assert_eq!(generated, !sent, "{}: {}", name, value);
+        }
+
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4711))));
+
+        assert!(
+            !resolve(
+                &parts(headers("01965864-f8ab-7eb8-912a-a2c999ab110e"), extensions),
+                &config
+            )
+            .unwrap()
+            .1
+        );
+    }
+
+    #[test]
+    fn test_resolve_enforcement_warn_only() {
+        let config = RequestIdConfig::default()
+            .with_enforcement(Enforcement::WarnOnly)
+            .with_require_id(|_| true);
+
+        for request_id in [
+            None,
+            Some("foo"),
+            Some(""),
+            Some("6edaba95-4f5b-4547-be3f-85210d3ff8bf"),
+            Some("00000000-0000-0000-0000-000000000000"),
+        ] {
+            let headers = request_id.map(headers).unwrap_or_default();
+
+            let (request_id, generated) =
+                resolve(&parts(headers, Extensions::new()), &config).unwrap();
+
+            assert!(generated);
+            assert_eq!(
+                Uuid::try_parse(&request_id).unwrap().get_version(),
+                Some(Version::SortRand)
+            );
+        }
+
+        assert_eq!(
+            resolve(
+                &parts(headers("foo"), Extensions::new()),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::NotUuid)
+        );
+    }
+
+    #[test]
+    fn test_resolve_version_header_shared_checks() {
+        let resolve_with = |request_id: &str, config: RequestIdConfig| {
+            let mut headers = headers(request_id);
+            headers.insert("x-request-id-version", HeaderValue::from_static("ulid"));
+
+            resolve(
+                &parts(headers, Extensions::new()),
+                &config.with_version_header(HeaderName::from_static("x-request-id-version")),
+            )
+        };
+
+        // The negotiated format doesn't bypass the checks shared with the UUIDs.
+        assert_eq!(
+            resolve_with(
+                &"0".repeat(1_000),
+                RequestIdConfig::default().with_max_len(64)
+            ),
+            Err(RequestIdError::TooLong)
+        );
+        assert_eq!(
+            resolve_with(
+                "01ARZ3NDEKTSV4RRFFQ69G5FAV",
+                RequestIdConfig::default().with_reserved_prefixes(vec!["01ARZ".to_string()])
+            ),
+            Err(RequestIdError::ReservedPrefix)
+        );
+        assert_eq!(
+            resolve_with("01ARZ3NDEKTSV\t4RRFFQ69G5FAV", RequestIdConfig::default()),
+            Err(RequestIdError::ControlCharacter)
+        );
+        assert_eq!(
+            resolve_with(
+                "01arz3ndektsv4rrffq69g5fav",
+                RequestIdConfig::default()
+                    .with_denied_ids([
+                        Uuid::try_parse("01563e3a-b5d3-d676-4c61-efb99302bd5b").unwrap()
+                    ])
+            ),
+            Err(RequestIdError::Denied)
+        );
+        assert_eq!(
+            resolve_with(
+                "01ARZ3NDEKTSV4RRFFQ69G5FAV",
+                RequestIdConfig::default().with_allowed_ids([Uuid::nil()])
+            ),
+            Err(RequestIdError::Denied)
+        );
+    }
+
+    #[test]
+    fn test_resolve_version_header() {
+        let config = RequestIdConfig::default()
+            .with_version_header(HeaderName::from_static("x-request-id-version"));
+
+        let resolve_with = |request_id: &str, version: &'static str| {
+            let mut headers = headers(request_id);
+            headers.insert("x-request-id-version", HeaderValue::from_static(version));
+
+            resolve(&parts(headers, Extensions::new()), &config)
+        };
+
+        assert_eq!(
+            resolve_with("01965864-f8ab-7eb8-912a-a2c999ab110e", "v7"),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+        assert_eq!(
+            resolve_with("01965864-f8ab-7eb8-912a-a2c999ab110e", "ulid"),
+            Err(RequestIdError::NotUlid)
+        );
+        assert_eq!(
+            resolve_with("01arz3ndektsv4rrffq69g5fav", "ULID"),
+            Ok(("01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string(), false))
+        );
+        assert_eq!(
+            resolve_with("81ARZ3NDEKTSV4RRFFQ69G5FAV", "ulid"),
+            Err(RequestIdError::NotUlid)
+        );
+        assert_eq!(
+            resolve_with("01ARZ3NDEKTSV4RRFFQ69G5FAV", "v7"),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            resolve_with("01965864-f8ab-7eb8-912a-a2c999ab110e", "v4"),
+            Err(RequestIdError::UnknownVersion)
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_nil_means_generate_id_prefix() {
+        let parts = parts(
+            headers("svc-00000000-0000-0000-0000-000000000000"),
+            Extensions::new(),
+        );
+
+        let (_, is_generated) = resolve(
+            &parts,
+            &RequestIdConfig::default()
+                .with_id_prefix("svc-")
+                .with_nil_means_generate(true),
+        )
+        .unwrap();
+
+        assert!(is_generated);
+    }
+
+    #[test]
+    fn test_resolve_nil_means_generate() {
+        for request_id in [
+            "00000000-0000-0000-0000-000000000000",
+            "00000000000000000000000000000000",
+            "{00000000-0000-0000-0000-000000000000}",
+        ] {
+            let parts = parts(headers(request_id), Extensions::new());
+
+            assert_eq!(
+                resolve(&parts, &RequestIdConfig::default()),
+                Err(RequestIdError::NotV7)
+            );
+
+            let (generated, is_generated) = resolve(
+                &parts,
+                &RequestIdConfig::default().with_nil_means_generate(true),
+            )
+            .unwrap();
+
+            assert!(is_generated);
+            assert_ne!(Uuid::try_parse(&generated).unwrap(), Uuid::nil());
+            assert_eq!(
+                Uuid::try_parse(&generated).unwrap().get_version(),
+                Some(Version::SortRand)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_strict_variant() {
+        let config = RequestIdConfig::default().with_strict_variant(true);
+
+        for (request_id, variant) in [
+            ("01965864-f8ab-7eb8-112a-a2c999ab110e", Variant::NCS),
+            ("01965864-f8ab-7eb8-d12a-a2c999ab110e", Variant::Microsoft),
+            ("01965864-f8ab-7eb8-f12a-a2c999ab110e", Variant::Future),
+        ] {
+            assert_eq!(
+                Uuid::try_parse(request_id).unwrap().get_version(),
+                Some(Version::SortRand)
+            );
+            assert_eq!(Uuid::try_parse(request_id).unwrap().get_variant(), variant);
+            assert!(validate_or_generate(Some(request_id), &RequestIdConfig::default()).is_ok());
+            assert_eq!(
+                validate_or_generate(Some(request_id), &config),
+                Err(RequestIdError::InvalidVariant)
+            );
+        }
+
+        assert!(
+            validate_or_generate(Some("01965864-f8ab-7eb8-912a-a2c999ab110e"), &config).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_resolve_denied_ids() {
+        let denied = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+        let config = RequestIdConfig::default().with_denied_ids([Uuid::try_parse(denied).unwrap()]);
+
+        assert_eq!(
+            validate_or_generate(Some(denied), &config),
+            Err(RequestIdError::Denied)
+        );
+        assert_eq!(
+            validate_or_generate(Some("01965864F8AB7EB8912AA2C999AB110E"), &config),
+            Err(RequestIdError::Denied)
+        );
+        assert_eq!(
+            validate_or_generate(Some("0196583c-4d2a-7087-9beb-6214d18ec924"), &config),
+            Ok(("0196583c-4d2a-7087-9beb-6214d18ec924".to_string(), false))
+        );
+        assert_eq!(RequestIdError::Denied.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_resolve_allowed_ids() {
+        let allowed = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+        let config =
+            RequestIdConfig::default().with_allowed_ids([Uuid::try_parse(allowed).unwrap()]);
+
+        assert_eq!(
+            validate_or_generate(Some(allowed), &config),
+            Ok((allowed.to_string(), false))
+        );
+        assert_eq!(
+            validate_or_generate(Some("0196583c-4d2a-7087-9beb-6214d18ec924"), &config),
+            Err(RequestIdError::Denied)
+        );
+        assert!(validate_or_generate(None, &config).is_ok());
+
+        let config = config.with_denied_ids([Uuid::try_parse(allowed).unwrap()]);
+
+        assert_eq!(
+            validate_or_generate(Some(allowed), &config),
+            Err(RequestIdError::Denied)
+        );
+    }
+
+    #[test]
+    fn test_resolve_fallback_id() {
+        let fallback_id =
+            RequestId::try_from_uuid(Uuid::from_u128(0x7000_8000_0000_0000_0000)).unwrap();
+        let config = RequestIdConfig::default()
+            .with_require_id(|_| true)
+            .with_fallback_id(fallback_id);
+
+        assert_eq!(
+            resolve(&parts(HeaderMap::new(), Extensions::new()), &config),
+            Ok(("00000000-0000-7000-8000-000000000000".to_string(), false))
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_not_uuid() {
+        assert_eq!(
+            resolve(
+                &parts(headers("01965864-f8ab-7eb8-912a"), Extensions::new()),
+                &RequestIdConfig::default()
+            ),
+            Err(RequestIdError::NotUuid)
+        );
+    }
+
+    #[test]
+    fn test_resolve_zero_timestamp_rejected() {
+        let request_id = Builder::from_unix_timestamp_millis(0, &[0x42; 10])
+            .into_uuid()
+            .to_string();
+        let config = RequestIdConfig::default().with_reject_zero_timestamp(true);
+
+        assert_eq!(
+            resolve(&parts(headers(&request_id), Extensions::new()), &config),
+            Err(RequestIdError::ZeroTimestamp)
+        );
+    }
+
+    #[test]
+    fn test_resolve_zero_timestamp_accepted() {
+        let request_id = Builder::from_unix_timestamp_millis(0, &[0x42; 10])
+            .into_uuid()
+            .to_string();
+        let config = RequestIdConfig::default();
+
+        assert_eq!(
+            resolve(&parts(headers(&request_id), Extensions::new()), &config),
+            Ok((request_id, false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_zero_random() {
+        let request_id = Builder::from_unix_timestamp_millis(1_745_239_734_443, &[0; 10])
+            .into_uuid()
+            .to_string();
+
+        assert_eq!(request_id, "01965864-f8ab-7000-8000-000000000000");
+        assert_eq!(
+            resolve(
+                &parts(headers(&request_id), Extensions::new()),
+                &RequestIdConfig::default()
+            ),
+            Ok((request_id.clone(), false))
+        );
+
+        let config = RequestIdConfig::default().with_reject_zero_random(true);
+
+        assert_eq!(
+            resolve(&parts(headers(&request_id), Extensions::new()), &config),
+            Err(RequestIdError::ZeroRandom)
+        );
+        assert_eq!(RequestIdError::ZeroRandom.status(), StatusCode::BAD_REQUEST);
+
+        let mut random_bytes = [0; 10];
+        random_bytes[9] = 1;
+
+        let request_id = Builder::from_unix_timestamp_millis(1_745_239_734_443, &random_bytes)
+            .into_uuid()
+            .to_string();
+
+        assert_eq!(
+            resolve(&parts(headers(&request_id), Extensions::new()), &config),
+            Ok((request_id, false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_from_trailers() {
+        let mut extensions = Extensions::new();
+
+        extensions.insert(RequestTrailers(headers(
+            "01965864-f8ab-7eb8-912a-a2c999ab110e",
+        )));
+
+        let config = RequestIdConfig::default().with_read_trailers(true);
+
+        assert_eq!(
+            resolve(&parts(HeaderMap::new(), extensions), &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_from_trailers_disabled() {
+        let mut extensions = Extensions::new();
+
+        extensions.insert(RequestTrailers(headers(
+            "01965864-f8ab-7eb8-912a-a2c999ab110e",
+        )));
+
+        let (request_id, generated) = resolve(
+            &parts(HeaderMap::new(), extensions),
+            &RequestIdConfig::default(),
+        )
+        .unwrap();
+
+        assert_ne!(request_id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+        assert!(generated);
+    }
+
+    #[test]
+    fn test_resolve_header_wins_over_trailers() {
+        let mut extensions = Extensions::new();
+
+        extensions.insert(RequestTrailers(headers(
+            "0196583c-4d2a-7087-9beb-6214d18ec924",
+        )));
+
+        let config = RequestIdConfig::default().with_read_trailers(true);
+
+        assert_eq!(
+            resolve(
+                &parts(headers("01965864-f8ab-7eb8-912a-a2c999ab110e"), extensions),
+                &config
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_uppercase_hyphenated() {
+        let config = RequestIdConfig::default()
+            .with_canonical_form(CanonicalForm::Hyphenated)
+            .with_normalize_case(LetterCase::Upper);
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864f8ab7eb8912aa2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("01965864-F8AB-7EB8-912A-A2C999AB110E".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_uppercase_simple() {
+        let config = RequestIdConfig::default()
+            .with_canonical_form(CanonicalForm::Simple)
+            .with_normalize_case(LetterCase::Upper);
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("01965864F8AB7EB8912AA2C999AB110E".to_string(), false))
+        );
+
+        let (generated, _) = resolve(&parts(HeaderMap::new(), Extensions::new()), &config).unwrap();
+
+        assert_eq!(generated.len(), 32);
+        assert!(
+            generated
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || byte.is_ascii_uppercase())
+        );
+        assert_eq!(
+            resolve(&parts(headers(&generated), Extensions::new()), &config),
+            Ok((generated.clone(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_braces_uppercase() {
+        let config = RequestIdConfig::default()
+            .with_canonical_form(CanonicalForm::Braces)
+            .with_normalize_case(LetterCase::Preserve);
+
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("{01965864-F8AB-7EB8-912A-A2C999AB110E}"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Ok(("{01965864-F8AB-7EB8-912A-A2C999AB110E}".to_string(), false))
+        );
+        assert_eq!(
+            resolve(
+                &parts(
+                    headers("{6EDABA95-4F5B-4547-BE3F-85210D3FF8BF}"),
+                    Extensions::new()
+                ),
+                &config
+            ),
+            Err(RequestIdError::NotV7)
+        );
+    }
+
+    #[test]
+    fn test_resolve_sources_precedence() {
+        let config = RequestIdConfig::default().with_sources(vec![
+            IdSource::Header(HeaderName::from_static("x-request-id")),
+            IdSource::Cookie("request_id".to_string()),
+            IdSource::Query("request_id".to_string()),
+        ]);
+
+        let request = |header: bool, cookie: bool| {
+            let mut request =
+                Request::builder().uri("/?foo=bar&request_id=0196583c-4d2a-7087-9beb-6214d18ec924");
+
+            if header {
+                request = request.header("x-request-id", "01965864-f8ab-7eb8-912a-a2c999ab110e");
+            }
+
+            if cookie {
+                request = request.header(
+                    "cookie",
+                    "foo=bar; request_id=01965865-1b2c-7d3e-8f40-5a6b7c8d9e0f",
+                );
+            }
+
+            request.body(()).unwrap().into_parts().0
+        };
+
+        assert_eq!(
+            resolve(&request(true, true), &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+        assert_eq!(
+            resolve(&request(false, true), &config),
+            Ok(("01965865-1b2c-7d3e-8f40-5a6b7c8d9e0f".to_string(), false))
+        );
+        assert_eq!(
+            resolve(&request(false, false), &config),
+            Ok(("0196583c-4d2a-7087-9beb-6214d18ec924".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_source_specs() {
+        let config = RequestIdConfig::default()
+            .with_canonical_form(CanonicalForm::Hyphenated)
+            .with_source(
+                IdSource::Header(HeaderName::from_static("x-request-id")).with_validate(true),
+            )
+            .with_source(IdSource::Cookie("rid".to_string()).with_validate(false))
+            .with_source(IdSource::Query("request_id".to_string()).with_normalize(false));
+
+        let request = |header: Option<&str>, cookie: Option<&str>| {
+            let mut request =
+                Request::builder().uri("/?request_id=01965864F8AB7EB8912AA2C999AB110E");
+
+            if let Some(header) = header {
+                request = request.header("x-request-id", header);
+            }
+
+            if let Some(cookie) = cookie {
+                request = request.header("cookie", format!("rid={}", cookie));
+            }
+
+            request.body(()).unwrap().into_parts().0
+        };
+
+        // The header is validated, so an invalid value is rejected rather than falling through.
+        assert_eq!(
+            resolve(&request(Some("legacy-42"), Some("legacy-42")), &config),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            resolve(
+                &request(Some("01965864f8ab7eb8912aa2c999ab110e"), None),
+                &config
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+
+        // The cookie isn't validated, only checked for control characters and normalized per the configured letter case.
+        assert_eq!(
+            resolve(&request(None, Some("Legacy-42")), &config),
+            Ok(("legacy-42".to_string(), false))
+        );
+        assert_eq!(
+            resolve(&request(None, Some("legacy\t42")), &config),
+            Err(RequestIdError::ControlCharacter)
+        );
+
+        // The query parameter is validated, but kept as sent.
+        assert_eq!(
+            resolve(&request(None, None), &config),
+            Ok(("01965864F8AB7EB8912AA2C999AB110E".to_string(), false))
+        );
+
+        assert_eq!(
+            config.configured_sources(),
+            vec![
+                "header:x-request-id",
+                "cookie:rid (unvalidated)",
+                "query:request_id (unnormalized)",
+                "generate"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_sources_http2_query() {
+        let config = RequestIdConfig::default()
+            .with_sources(vec![IdSource::Query("request_id".to_string())]);
+
+        // HTTP/2 requests carry the target in the `:scheme`, `:authority` and `:path` pseudo-headers, which hyper turns into an absolute URI.
+        let (parts, _) = Request::builder()
+            .version(axum::http::Version::HTTP_2)
+            .uri("https://example.com/foo?request_id=01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        assert!(!parts.headers.contains_key("x-request-id"));
+        assert_eq!(
+            resolve(&parts, &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_sources_grpc_web() {
+        let config = RequestIdConfig::default().with_sources(vec![
+            IdSource::Header(HeaderName::from_static("x-request-id")),
+            IdSource::Header(HeaderName::from_static("grpc-metadata-x-request-id")),
+        ]);
+
+        let request = Request::builder()
+            .uri("/helloworld.Greeter/SayHello")
+            .header("Content-Type", "application/grpc-web+proto")
+            .header("X-REQUEST-ID", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(&request.into_parts().0, &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+
+        let request = Request::builder()
+            .uri("/helloworld.Greeter/SayHello")
+            .header("Content-Type", "application/connect+json")
+            .header(
+                "Grpc-Metadata-X-Request-Id",
+                "0196583c-4d2a-7087-9beb-6214d18ec924",
+            )
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(&request.into_parts().0, &config),
+            Ok(("0196583c-4d2a-7087-9beb-6214d18ec924".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_sources_traceparent() {
+        let config = RequestIdConfig::default().with_sources(vec![IdSource::Traceparent]);
+        let request = Request::builder()
+            .header(
+                "traceparent",
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            )
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(&request.into_parts().0, &config),
+            Ok(("4bf92f35-77b3-4da6-a3ce-929d0e0e4736".to_string(), false))
+        );
+
+        let request = Request::builder()
+            .header("traceparent", "00-not-a-trace-id-01")
+            .body(())
+            .unwrap();
+
+        assert!(resolve(&request.into_parts().0, &config).unwrap().1);
+    }
+
+    #[test]
+    fn test_resolve_sources_jwt_id() {
+        let config = RequestIdConfig::default().with_sources(vec![
+            IdSource::Header(HeaderName::from_static("x-request-id")),
+            IdSource::JwtId,
+        ]);
+
+        // {"alg":"none"}.{"sub":"foo","jti":"01965864-f8ab-7eb8-912a-a2c999ab110e"}.
+        let request = Request::builder()
+            .header(
+                "authorization",
+                "Bearer eyJhbGciOiJub25lIn0.eyJzdWIiOiJmb28iLCJqdGkiOiIwMTk2NTg2NC1mOGFiLTdlYjgtOTEyYS1hMmM5OTlhYjExMGUifQ.",
+            )
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(&request.into_parts().0, &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+
+        let request = Request::builder()
+            .header("authorization", "Basic Zm9vOmJhcg==")
+            .body(())
+            .unwrap();
+
+        assert!(resolve(&request.into_parts().0, &config).unwrap().1);
+    }
+
+    #[test]
+    fn test_resolve_validate_with_warnings() {
+        let config = RequestIdConfig::default()
+            .with_canonical_form(CanonicalForm::Hyphenated)
+            .with_percent_decode(true);
+
+        assert_eq!(
+            validate_with_warnings(" %7B01965864-F8AB-7EB8-912A-A2C999AB110E%7D\t", &config),
+            Ok((
+                "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(),
+                vec![
+                    ValidationWarning::Whitespace,
+                    ValidationWarning::PercentEncoded,
+                    ValidationWarning::Uppercase,
+                    ValidationWarning::Braced
+                ]
+            ))
+        );
+        assert_eq!(
+            validate_with_warnings("01965864-f8ab-7eb8-912a-a2c999ab110e", &config),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), vec![]))
+        );
+        assert_eq!(
+            validate_with_warnings("foo", &config),
+            Err(RequestIdError::NotUuid)
+        );
+    }
+
+    #[test]
+    fn test_resolve_sources_amzn_request_id() {
+        let config = RequestIdConfig::default().with_sources(vec![
+            IdSource::Header(HeaderName::from_static("x-request-id")),
+            IdSource::AmznRequestId,
+        ]);
+
+        let request = |amzn_request_id: &str| {
+            Request::builder()
+                .header("X-Amzn-RequestId", amzn_request_id)
+                .body(())
+                .unwrap()
+                .into_parts()
+                .0
+        };
+
+        assert_eq!(
+            resolve(&request(" 4442587FB7D0A2F9 "), &config),
+            Ok(("4442587fb7d0a2f9".to_string(), false))
+        );
+        assert_eq!(
+            resolve(
+                &request("c6af9ac6-7b61-11e6-9a41-93E8DEADBEEF"),
+                &config.clone().with_normalize_case(LetterCase::Preserve)
+            ),
+            Ok(("c6af9ac6-7b61-11e6-9a41-93E8DEADBEEF".to_string(), false))
+        );
+        assert_eq!(
+            resolve(&request("4442587F\tB7D0A2F9"), &config),
+            Err(RequestIdError::ControlCharacter)
+        );
+    }
+
+    #[test]
+    fn test_resolve_validate_or_generate_valid() {
+        assert_eq!(
+            validate_or_generate(
+                Some(" 01965864-F8AB-7EB8-912A-A2C999AB110E "),
+                &RequestIdConfig::default()
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_validate_or_generate_invalid() {
+        let config = RequestIdConfig::default().with_reject_zero_timestamp(true);
+
+        assert_eq!(
+            validate_or_generate(Some("foo"), &config),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            validate_or_generate(Some("6edaba95-4f5b-4547-be3f-85210d3ff8bf"), &config),
+            Err(RequestIdError::NotV7)
+        );
+        assert_eq!(
+            validate_or_generate(
+                Some(
+                    &Builder::from_unix_timestamp_millis(0, &[0x42; 10])
+                        .into_uuid()
+                        .to_string()
+                ),
+                &config
+            ),
+            Err(RequestIdError::ZeroTimestamp)
+        );
+    }
+
+    #[test]
+    fn test_resolve_validate_or_generate_missing() {
+        let (request_id, generated) =
+            validate_or_generate(None, &RequestIdConfig::default()).unwrap();
+
+        assert!(generated);
+        assert_eq!(
+            Uuid::try_parse(&request_id).unwrap().get_version(),
+            Some(Version::SortRand)
+        );
+    }
+
+    #[test]
+    fn test_resolve_ensure_request_id_present_valid() {
+        let mut headers = headers("01965864F8AB7EB8912AA2C999AB110E");
+        let config = RequestIdConfig::default().with_canonical_form(CanonicalForm::Hyphenated);
+
+        assert_eq!(
+            ensure_request_id(&mut headers, &config),
+            Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())
+        );
+        assert_eq!(
+            headers["x-request-id"],
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_resolve_ensure_request_id_present_invalid() {
+        let mut headers = headers("this-is-not-a-uuid");
+
+        assert_eq!(
+            ensure_request_id(&mut headers, &RequestIdConfig::default()),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(headers["x-request-id"], "this-is-not-a-uuid");
+    }
+
+    #[test]
+    fn test_resolve_ensure_request_id_absent() {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("accept", HeaderValue::from_static("*/*"));
+
+        let request_id = ensure_request_id(&mut headers, &RequestIdConfig::default()).unwrap();
+
+        assert_eq!(headers["x-request-id"], request_id);
+        assert_eq!(headers["accept"], "*/*");
+    }
+
+    #[test]
+    fn test_resolve_extract_and_echo() {
+        let config = RequestIdConfig::default();
+        let mut response_headers = HeaderMap::new();
+
+        assert_eq!(
+            extract_and_echo(
+                &headers("01965864-f8ab-7eb8-912a-a2c999ab110e"),
+                &mut response_headers,
+                &config
+            ),
+            Ok(RequestId::from(
+                Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap()
+            ))
+        );
+        assert_eq!(
+            response_headers["x-request-id"],
+            "01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+
+        let mut response_headers = HeaderMap::new();
+
+        let generated =
+            extract_and_echo(&HeaderMap::new(), &mut response_headers, &config).unwrap();
+
+        assert_eq!(
+            response_headers["x-request-id"],
+            generated.to_string().as_str()
+        );
+
+        let mut response_headers = HeaderMap::new();
+
+        assert_eq!(
+            extract_and_echo(
+                &headers("this-is-not-a-uuid"),
+                &mut response_headers,
+                &config
+            ),
+            Err(RequestIdError::NotUuid)
+        );
+        assert!(response_headers.is_empty());
+
+        let mut response_headers = HeaderMap::new();
+
+        extract_and_echo(
+            &headers("svc-01965864-f8ab-7eb8-912a-a2c999ab110e"),
+            &mut response_headers,
+            &RequestIdConfig::default().with_id_prefix("svc-"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response_headers["x-request-id"],
+            "svc-01965864-f8ab-7eb8-912a-a2c999ab110e"
+        );
+    }
+
+    #[test]
+    fn test_resolve_length_fast_rejection() {
+        let config = RequestIdConfig::default();
+
+        for request_id in [
+            "01965864-f8ab-7eb8-912a-a2c999ab110",
+            "01965864-f8ab-7eb8-912a-a2c999ab110e0",
+            &"0".repeat(1_000),
+        ] {
+            assert_eq!(
+                validate_or_generate(Some(request_id), &config),
+                Err(RequestIdError::NotUuid)
+            );
+        }
+
+        for request_id in [
+            "01965864f8ab7eb8912aa2c999ab110e",
+            "01965864-f8ab-7eb8-912a-a2c999ab110e",
+            "{01965864-f8ab-7eb8-912a-a2c999ab110e}",
+            "urn:uuid:01965864-f8ab-7eb8-912a-a2c999ab110e",
+        ] {
+            assert!(validate_or_generate(Some(request_id), &config).is_ok());
+        }
+
+        assert_eq!(
+            CANONICAL_UUID_LEN,
+            "01965864-f8ab-7eb8-912a-a2c999ab110e".len()
+        );
+    }
+
+    #[test]
+    fn test_resolve_max_len() {
+        let config = RequestIdConfig::default()
+            .with_trust_level(TrustLevel::Internal)
+            .with_max_len(64);
+
+        assert_eq!(
+            validate_or_generate(Some(&"a".repeat(64)), &config),
+            Ok(("a".repeat(64), false))
+        );
+
+        let err = validate_or_generate(Some(&"a".repeat(65)), &config).unwrap_err();
+
+        assert_eq!(err, RequestIdError::TooLong);
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-amzn-requestid",
+            HeaderValue::from_str(&"A".repeat(65)).unwrap(),
+        );
+
+        assert_eq!(
+            resolve(
+                &parts(headers, Extensions::new()),
+                &RequestIdConfig::default()
+                    .with_sources(vec![IdSource::AmznRequestId])
+                    .with_max_len(64)
+            ),
+            Err(RequestIdError::TooLong)
+        );
+        assert_eq!(
+            validate_or_generate(Some(&"0".repeat(1_000)), &RequestIdConfig::default()),
+            Err(RequestIdError::NotUuid)
+        );
+
+        // The decoded value is checked, i.e. 65 bytes here.
+        let request = Request::builder()
+            .uri(format!("/?request_id={}", "%61".repeat(65)))
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(
+                &request.into_parts().0,
+                &RequestIdConfig::default()
+                    .with_sources(vec![IdSource::Query("request_id".to_string())])
+                    .with_max_len(64)
+            ),
+            Err(RequestIdError::TooLong)
+        );
+
+        // {"alg":"none"}.{"jti":"aaa...a"}, with 65 bytes in the jti claim.
+        let request = Request::builder()
+            .header(
+                "authorization",
+                "Bearer eyJhbGciOiJub25lIn0.eyJqdGkiOiJhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYWFhYSJ9.",
+            )
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            resolve(
+                &request.into_parts().0,
+                &RequestIdConfig::default()
+                    .with_sources(vec![IdSource::JwtId])
+                    .with_max_len(64)
+            ),
+            Err(RequestIdError::TooLong)
+        );
+
+        // The negotiated format is checked after the length.
+        let mut ulid_headers = HeaderMap::new();
+        ulid_headers.insert(
+            "x-request-id",
+            HeaderValue::from_str(&"0".repeat(65)).unwrap(),
+        );
+        ulid_headers.insert("x-request-id-version", HeaderValue::from_static("ulid"));
+
+        assert_eq!(
+            resolve(
+                &parts(ulid_headers, Extensions::new()),
+                &RequestIdConfig::default()
+                    .with_version_header(HeaderName::from_static("x-request-id-version"))
+                    .with_max_len(64)
+            ),
+            Err(RequestIdError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_resolve_percent_decode() {
+        let request_id = "01965864%2Df8ab%2D7eb8%2D912a%2Da2c999ab110e";
+
+        assert_eq!(
+            validate_or_generate(Some(request_id), &RequestIdConfig::default()),
+            Err(RequestIdError::NotUuid)
+        );
+        assert_eq!(
+            validate_or_generate(
+                Some(request_id),
+                &RequestIdConfig::default().with_percent_decode(true)
+            ),
+            Ok(("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn test_resolve_canonicalize_batch() {
+        assert_eq!(
+            canonicalize_batch(&[
+                " 01965864-f8ab-7eb8-912a-a2c999ab110e ",
+                "{01965864-F8AB-7EB8-912A-A2C999AB110E}",
+                "urn:uuid:01965864-f8ab-7eb8-912a-a2c999ab110e",
+                "01965864F8AB7EB8912AA2C999AB110E",
+                "6edaba95-4f5b-4547-be3f-85210d3ff8bf",
+                "this-is-not-a-uuid",
+            ]),
+            vec![
+                Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()),
+                Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()),
+                Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()),
+                Ok("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()),
+                Err(RequestIdError::NotV7),
+                Err(RequestIdError::NotUuid),
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderMap, request::Parts},
+};
+
+use crate::{ExtractRequestId, RequestIdConfig, RequestIdRejection, config::with_default_config};
+
+/// The trace-sampling decision of a request, inserted into the request extensions by the layer,
+/// when `RequestIdConfig::with_sampling_header` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplingDecision(pub bool);
+
+/// Reads the trace-sampling decision from the configured header, or falls back to the configured default.
+pub(crate) fn sampling_decision(headers: &HeaderMap, config: &RequestIdConfig) -> bool {
+    let value = config
+        .sampling_header()
+        .and_then(|sampling_header| headers.get(sampling_header))
+        .and_then(|value| value.to_str().ok());
+
+    match value.map(str::trim) {
+        Some("1") | Some("true") => true,
+        Some("0") | Some("false") => false,
+        _ => config.sampled_default(),
+    }
+}
+
+/// This is a custom extractor for Axum that extracts the request id, exactly like `ExtractRequestId`,
+/// along with the trace-sampling decision of the header set via `RequestIdConfig::with_sampling_header`.
+///
+/// If a `RequestIdLayer` already read the sampling decision, it returns the same value.
+/// Otherwise, it uses the process-global configuration, and the default decision if no sampling header is configured.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractRequestIdSampled, RequestIdConfig, RequestIdLayer};
+///
+/// async fn handler(ExtractRequestIdSampled { id, sampled }: ExtractRequestIdSampled) {
+///     if sampled {
+///         println!("Request Id: {:?}", id);
+///     }
+/// }
+///
+/// let app = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(RequestIdLayer::new(
+///         RequestIdConfig::default().with_sampling_header("x-sampled".parse().unwrap()),
+///     ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractRequestIdSampled {
+    /// The request id.
+    pub id: String,
+    /// Whether the request is sampled.
+    pub sampled: bool,
+}
+
+impl<S> FromRequestParts<S> for ExtractRequestIdSampled
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractRequestId(id) = ExtractRequestId::from_request_parts(parts, state).await?;
+
+        let sampled = match parts.extensions.get::<SamplingDecision>() {
+            Some(SamplingDecision(sampled)) => *sampled,
+            None => with_default_config(|config| sampling_decision(&parts.headers, config)),
+        };
+
+        Ok(ExtractRequestIdSampled { id, sampled })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtractRequestIdSampled, RequestIdConfig, RequestIdLayer};
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    async fn handler(ExtractRequestIdSampled { sampled, .. }: ExtractRequestIdSampled) -> String {
+        sampled.to_string()
+    }
+
+    async fn sample(
+        config: RequestIdConfig,
+        sampled: Option<&str>,
+    ) -> (StatusCode, Option<String>, String) {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RequestIdLayer::new(config));
+
+        let mut request = Request::builder().uri("/");
+
+        if let Some(sampled) = sampled {
+            request = request.header("X-Sampled", sampled);
+        }
+
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let echoed = response
+            .headers()
+            .get("x-sampled")
+            .map(|value| value.to_str().unwrap().to_string());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (status, echoed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sampled_header_present() {
+        let config = RequestIdConfig::default().with_sampling_header("x-sampled".parse().unwrap());
+
+        assert_eq!(
+            sample(config.clone(), Some("1")).await,
+            (StatusCode::OK, Some("1".to_string()), "true".to_string())
+        );
+        assert_eq!(
+            sample(config, Some("false")).await,
+            (StatusCode::OK, Some("0".to_string()), "false".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sampled_header_absent() {
+        let config = RequestIdConfig::default().with_sampling_header("x-sampled".parse().unwrap());
+
+        assert_eq!(
+            sample(config.clone(), None).await,
+            (StatusCode::OK, Some("0".to_string()), "false".to_string())
+        );
+        assert_eq!(
+            sample(config.with_sampled_default(true), None).await,
+            (StatusCode::OK, Some("1".to_string()), "true".to_string())
+        );
+    }
+}
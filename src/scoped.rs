@@ -0,0 +1,193 @@
+use std::marker::PhantomData;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{RequestIdConfig, RequestIdRejection, config::with_default_config, resolve::resolve};
+
+/// The default marker of `ExtractScopedRequestId`, for the common case of a single scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DefaultScope;
+
+/// The configuration of the request id for the sub-router marked by `M`, inserted via an `Extension` layer.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{Extension, routing::get, Router};
+/// use request_id_middleware::{ExtractScopedRequestId, RequestIdConfig, ScopedConfig};
+///
+/// struct Admin;
+///
+/// async fn handler(ExtractScopedRequestId { id, .. }: ExtractScopedRequestId<Admin>) -> String {
+///     id
+/// }
+///
+/// let admin = Router::<()>::new()
+///     .route("/foo", get(handler))
+///     .layer(Extension(ScopedConfig::<Admin>::new(
+///         RequestIdConfig::default().with_header_name("x-admin-request-id".parse().unwrap()),
+///     )));
+///
+/// let app = Router::<()>::new().nest("/admin", admin);
+/// ```
+#[derive(Debug)]
+pub struct ScopedConfig<M> {
+    config: RequestIdConfig,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> ScopedConfig<M> {
+    /// Creates the configuration of the scope marked by `M`.
+    pub fn new(config: RequestIdConfig) -> Self {
+        Self {
+            config,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the configuration of the scope.
+    pub fn config(&self) -> &RequestIdConfig {
+        &self.config
+    }
+}
+
+impl<M> Clone for ScopedConfig<M> {
+    fn clone(&self) -> Self {
+        Self::new(self.config.clone())
+    }
+}
+
+/// This is a custom extractor for Axum that extracts the request id per the configuration of the scope marked by `M`.
+///
+/// It lets nested routers require different configurations, with a compile-time distinct extractor per sub-router.
+/// The configuration is read from the `ScopedConfig<M>` extension, or from the process-global one if it's missing.
+///
+/// Each scope resolves its request id independently, so it doesn't share the `ResolvedRequestId` of `ExtractRequestId`
+/// and `RequestIdLayer`, and the freshness check is skipped.
+#[derive(Debug)]
+pub struct ExtractScopedRequestId<M = DefaultScope> {
+    /// The request id.
+    pub id: String,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> Clone for ExtractScopedRequestId<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// The request id resolved for the scope marked by `M`, so that later extractions return the same value.
+struct ScopedRequestId<M> {
+    id: String,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> Clone for ScopedRequestId<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<S, M> FromRequestParts<S> for ExtractScopedRequestId<M>
+where
+    S: Send + Sync,
+    M: 'static,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(scoped) = parts.extensions.get::<ScopedRequestId<M>>() {
+            return Ok(Self {
+                id: scoped.id.clone(),
+                marker: PhantomData,
+            });
+        }
+
+        let resolved = |config: &RequestIdConfig| {
+            resolve(parts, config)
+                .map_err(|err| RequestIdRejection::with_config(err, config, &parts.headers))
+        };
+
+        let (id, _) = match parts.extensions.get::<ScopedConfig<M>>() {
+            Some(scoped) => resolved(scoped.config()),
+            None => with_default_config(resolved),
+        }?;
+
+        parts.extensions.insert(ScopedRequestId::<M> {
+            id: id.clone(),
+            marker: PhantomData,
+        });
+
+        Ok(Self {
+            id,
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtractScopedRequestId, RequestIdConfig, ScopedConfig};
+    use axum::{
+        body::Body,
+        extract::FromRequestParts,
+        http::{HeaderName, Request},
+    };
+
+    struct Admin;
+
+    struct Public;
+
+    #[tokio::test]
+    async fn test_scoped_markers_resolve_from_their_own_config() {
+        let mut parts = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .header("X-Admin-Request-Id", "0196583c-4d2a-7087-9beb-6214d18ec924")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        parts.extensions.insert(ScopedConfig::<Admin>::new(
+            RequestIdConfig::default()
+                .with_header_name(HeaderName::from_static("x-admin-request-id")),
+        ));
+        parts
+            .extensions
+            .insert(ScopedConfig::<Public>::new(RequestIdConfig::default()));
+
+        let admin = ExtractScopedRequestId::<Admin>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        let public = ExtractScopedRequestId::<Public>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+
+        assert_eq!(admin.id, "0196583c-4d2a-7087-9beb-6214d18ec924");
+        assert_eq!(public.id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+
+    #[tokio::test]
+    async fn test_scoped_default_marker() {
+        let mut parts = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        let ExtractScopedRequestId { id, .. } =
+            <ExtractScopedRequestId>::from_request_parts(&mut parts, &())
+                .await
+                .unwrap();
+
+        assert_eq!(id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+    }
+}
@@ -0,0 +1,329 @@
+use std::{borrow::Cow, fmt};
+
+use axum::http::{
+    HeaderName, HeaderValue,
+    header::{AUTHORIZATION, COOKIE},
+    request::Parts,
+};
+use log::{error, warn};
+use uuid::Uuid;
+
+use crate::{RequestIdError, RequestTrailers};
+
+pub(crate) const HEADER_TRACEPARENT: &str = "traceparent";
+const HEADER_X_AMZN_REQUEST_ID: &str = "x-amzn-requestid";
+
+/// An extraction point the request id is read from, see `RequestIdConfig::with_sources` and `RequestIdConfig::with_source`.
+///
+/// The sources are tried in order, the first one carrying a value wins, and a new UUID v7 is generated when none does.
+/// A value found in a source is validated, and the request is rejected if it's invalid, i.e. it doesn't fall through to the next source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdSource {
+    /// A request header, e.g. `X-Request-Id`.
+    ///
+    /// Header names are case-insensitive, so gRPC-Web and Connect requests, which carry their metadata as HTTP headers,
+    /// are read as is, whatever their casing. Proxies prefixing the metadata, e.g. `grpc-metadata-x-request-id`
+    /// for grpc-gateway, are supported by adding that header as another source.
+    Header(HeaderName),
+    /// A cookie, from the `Cookie` request headers.
+    Cookie(String),
+    /// A query string parameter, e.g. for clients that can't set custom headers.
+    ///
+    /// It's read from the request URI, so it works the same for HTTP/2 requests, where the query string is part of the `:path` pseudo-header.
+    Query(String),
+    /// The trace id of the W3C `traceparent` header, formatted as an hyphenated UUID.
+    ///
+    /// Trace ids are random 128-bit values rather than UUIDs v7, so they're not validated as such.
+    /// A malformed `traceparent` is ignored.
+    Traceparent,
+    /// A request trailer, from the `RequestTrailers` extension.
+    Trailer(HeaderName),
+    /// The `jti` claim of the JWT bearer token of the `Authorization` header, validated as the configured format.
+    ///
+    /// The token is only decoded, not verified, so it must be authenticated by another layer.
+    /// A malformed token, or one without a `jti` claim, is ignored.
+    JwtId,
+    /// The `X-Amzn-RequestId` header emitted by AWS services, passed through without validating it as an UUID.
+    ///
+    /// AWS request ids come in various formats, e.g. `4442587FB7D0A2F9` for S3, so the value is only trimmed,
+    /// checked for control characters, and its case normalized per `RequestIdConfig::with_normalize_case`.
+    /// Use `LetterCase::Preserve` to keep it exactly as AWS sent it, e.g. to look it up with AWS support.
+    AmznRequestId,
+}
+
+/// A source of the chain the request id is read from, together with how its value is handled, see `RequestIdConfig::with_source`.
+///
+/// By default, the value is validated and normalized like any other, so an `IdSource` converts into a `SourceSpec` as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::http::HeaderName;
+/// use request_id_middleware::{IdSource, RequestIdConfig};
+///
+/// let config = RequestIdConfig::default()
+///     .with_source(IdSource::Header(HeaderName::from_static("x-request-id")))
+///     .with_source(IdSource::Cookie("rid".to_string()).with_validate(false));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpec {
+    source: IdSource,
+    validate: bool,
+    normalize: bool,
+}
+
+impl SourceSpec {
+    /// Creates the spec of the given source, validated and normalized.
+    pub fn new(source: IdSource) -> Self {
+        Self {
+            source,
+            validate: true,
+            normalize: true,
+        }
+    }
+
+    /// Sets whether the value is validated as the configured format (default `true`).
+    ///
+    /// Otherwise, it's only trimmed and checked for control characters, like `IdSource::AmznRequestId`,
+    /// so the typed extractors, e.g. `RequestId`, reject it unless it's an UUID.
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets whether the value is normalized per the configured canonical form and letter case (default `true`).
+    ///
+    /// Otherwise, it's kept as sent, only trimmed and stripped of the configured id prefix.
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Returns the source.
+    pub fn source(&self) -> &IdSource {
+        &self.source
+    }
+
+    /// Returns whether the value is validated as the configured format.
+    pub fn validate(&self) -> bool {
+        self.validate
+    }
+
+    /// Returns whether the value is normalized per the configured canonical form and letter case.
+    pub fn normalize(&self) -> bool {
+        self.normalize
+    }
+}
+
+impl From<IdSource> for SourceSpec {
+    fn from(source: IdSource) -> Self {
+        Self::new(source)
+    }
+}
+
+/// Describes the source like `IdSource` does, followed by the disabled steps, e.g. `cookie:rid (unvalidated)`.
+impl fmt::Display for SourceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)?;
+
+        match (self.validate, self.normalize) {
+            (true, true) => Ok(()),
+            (false, true) => write!(f, " (unvalidated)"),
+            (true, false) => write!(f, " (unnormalized)"),
+            (false, false) => write!(f, " (unvalidated, unnormalized)"),
+        }
+    }
+}
+
+/// A value found in a source.
+pub(crate) enum Found<'a> {
+    /// A value sent by the client, to be validated.
+    Sent(Cow<'a, str>),
+    /// A value already parsed from a source that isn't validated as an UUID v7.
+    Trusted(Uuid),
+    /// A value that isn't an UUID, only normalized.
+    PassThrough(Cow<'a, str>),
+}
+
+impl IdSource {
+    /// Returns the spec of this source, with the validation enabled or not, see `SourceSpec::with_validate`.
+    pub fn with_validate(self, validate: bool) -> SourceSpec {
+        SourceSpec::new(self).with_validate(validate)
+    }
+
+    /// Returns the spec of this source, with the normalization enabled or not, see `SourceSpec::with_normalize`.
+    pub fn with_normalize(self, normalize: bool) -> SourceSpec {
+        SourceSpec::new(self).with_normalize(normalize)
+    }
+
+    /// Returns the value carried by the request for this source, if any, or the reason why it can't be read.
+    pub(crate) fn find<'a>(&self, parts: &'a Parts) -> Option<Result<Found<'a>, RequestIdError>> {
+        match self {
+            IdSource::Header(name) => parts.headers.get(name).map(|value| to_str(name, value)),
+            IdSource::Trailer(name) => parts
+                .extensions
+                .get::<RequestTrailers>()
+                .and_then(|trailers| trailers.0.get(name))
+                .map(|value| to_str(name, value)),
+            IdSource::Cookie(name) => parts
+                .headers
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|cookies| cookies.split(';'))
+                .find_map(|cookie| {
+                    let (key, value) = cookie.split_once('=')?;
+
+                    (key.trim() == name).then(|| Ok(Found::Sent(Cow::Borrowed(value.trim()))))
+                }),
+            IdSource::Query(name) => form_urlencoded::parse(parts.uri.query()?.as_bytes())
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| Ok(Found::Sent(value))),
+            IdSource::Traceparent => parts
+                .headers
+                .get(HEADER_TRACEPARENT)
+                .and_then(|value| parse_traceparent(value.to_str().ok()?))
+                .map(|trace_id| Ok(Found::Trusted(trace_id))),
+            IdSource::JwtId => parts
+                .headers
+                .get(AUTHORIZATION)
+                .and_then(|value| parse_jwt_id(value.to_str().ok()?))
+                .map(|jti| Ok(Found::Sent(Cow::Owned(jti)))),
+            IdSource::AmznRequestId => {
+                let name = HeaderName::from_static(HEADER_X_AMZN_REQUEST_ID);
+
+                parts
+                    .headers
+                    .get(&name)
+                    .map(|value| match to_str(&name, value)? {
+                        Found::Sent(value) => Ok(Found::PassThrough(value)),
+                        found => Ok(found),
+                    })
+            }
+        }
+    }
+}
+
+/// Describes the source, e.g. `header:x-request-id` or `cookie:request_id`, see `RequestIdConfig::configured_sources`.
+impl fmt::Display for IdSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdSource::Header(name) => write!(f, "header:{}", name),
+            IdSource::Cookie(name) => write!(f, "cookie:{}", name),
+            IdSource::Query(name) => write!(f, "query:{}", name),
+            IdSource::Traceparent => write!(f, "header:{}", HEADER_TRACEPARENT),
+            IdSource::Trailer(name) => write!(f, "trailer:{}", name),
+            IdSource::JwtId => write!(f, "jwt:jti"),
+            IdSource::AmznRequestId => write!(f, "header:{}", HEADER_X_AMZN_REQUEST_ID),
+        }
+    }
+}
+
+fn to_str<'a>(name: &HeaderName, value: &'a HeaderValue) -> Result<Found<'a>, RequestIdError> {
+    match value.to_str() {
+        Ok(value) => Ok(Found::Sent(Cow::Borrowed(value))),
+        Err(err) => {
+            error!("[{}] Failed to read header due to : {:?}", name, err);
+
+            Err(RequestIdError::NotAscii)
+        }
+    }
+}
+
+/// Parses the trace id of a `traceparent` header, i.e. `<version>-<trace-id>-<parent-id>-<flags>`.
+fn parse_traceparent(traceparent: &str) -> Option<Uuid> {
+    let trace_id = traceparent
+        .trim()
+        .split('-')
+        .nth(1)
+        .filter(|trace_id| {
+            trace_id.len() == 32 && trace_id.bytes().all(|byte| byte.is_ascii_hexdigit())
+        })
+        .and_then(|trace_id| u128::from_str_radix(trace_id, 16).ok())
+        .filter(|trace_id| *trace_id != 0);
+
+    if trace_id.is_none() {
+        warn!(
+            "[{}] Ignoring malformed header : {}",
+            HEADER_TRACEPARENT, traceparent
+        );
+    }
+
+    trace_id.map(Uuid::from_u128)
+}
+
+/// Parses the `jti` claim of a bearer token, i.e. `Bearer <header>.<payload>.<signature>`.
+fn parse_jwt_id(authorization: &str) -> Option<String> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    let jti = token
+        .trim()
+        .split('.')
+        .nth(1)
+        .and_then(decode_base64_url)
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|claims| claims.get("jti")?.as_str().map(str::to_string));
+
+    if jti.is_none() {
+        warn!(
+            "[{}] Ignoring bearer token without a jti claim",
+            AUTHORIZATION
+        );
+    }
+
+    jti
+}
+
+/// Decodes unpadded base64url, per RFC 4648 section 5, as used by the JWT segments.
+fn decode_base64_url(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in encoded.trim_end_matches('=').bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Synthesizes a sampled `traceparent` header from a request id, i.e. `00-<request id>-<last 8 bytes>-01`.
+pub(crate) fn synthesize_traceparent(request_id: &Uuid) -> String {
+    let simple = request_id.simple().to_string();
+
+    format!("00-{}-{}-01", simple, &simple[16..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::decode_base64_url;
+
+    #[test]
+    fn test_source_decode_base64_url() {
+        assert_eq!(
+            decode_base64_url("eyJmb28iOiJiYXIifQ"),
+            Some(br#"{"foo":"bar"}"#.to_vec())
+        );
+        assert_eq!(decode_base64_url("-_8"), Some(vec![0xfb, 0xff]));
+        assert_eq!(decode_base64_url("not base64"), None);
+    }
+}
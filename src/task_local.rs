@@ -0,0 +1,157 @@
+use std::{cell::RefCell, marker::PhantomData, thread};
+
+use log::error;
+
+use crate::config::with_default_config;
+
+tokio::task_local! {
+    /// The request id of the request being handled, set by `RequestIdLayer`.
+    pub(crate) static CURRENT_REQUEST_ID: String;
+}
+
+thread_local! {
+    /// The request id set by the innermost `RequestIdGuard` of the current thread, if any.
+    static GUARDED_REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Returns the request id of the request being handled by the current task, if any, e.g. from a custom panic hook.
+///
+/// It's set by `RequestIdLayer` for as long as its inner service's future runs, i.e. the inner layers and the handler,
+/// on the task polling that future, and by a `RequestIdGuard` for as long as it's alive, on its thread. It's `None` elsewhere, in particular :
+///
+/// - in tasks spawned by the handler, e.g. via `tokio::spawn`, unless they're scoped again ;
+/// - once the response is returned, e.g. while its body is streamed ;
+/// - outside of a `RequestIdLayer`, e.g. with the `ExtractRequestId` extractor alone.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::current_request_id;
+///
+/// std::panic::set_hook(Box::new(|info| {
+///     eprintln!("[{:?}] {}", current_request_id(), info);
+/// }));
+/// ```
+pub fn current_request_id() -> Option<String> {
+    GUARDED_REQUEST_ID
+        .with_borrow(Clone::clone)
+        .or_else(|| CURRENT_REQUEST_ID.try_with(Clone::clone).ok())
+}
+
+/// A guard setting the request id returned by `current_request_id` on the current thread, until it's dropped.
+///
+/// It covers the code the task-local of `RequestIdLayer` doesn't, e.g. a `tokio::task::spawn_blocking` closure or a thread
+/// of a pool, so that a panic hook reads the request id there too. If it's dropped while unwinding, it also logs the request id,
+/// to tie the panic to the request. The previous request id, if any, is restored once it's dropped, so guards can be nested.
+///
+/// It's bound to its thread, so it can't be held across an `.await` of a multi-threaded runtime, which may resume on another thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::{RequestIdGuard, current_request_id};
+///
+/// let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e".to_string();
+///
+/// let handle = std::thread::spawn(move || {
+///     let _guard = RequestIdGuard::new(request_id);
+///
+///     current_request_id()
+/// });
+///
+/// assert_eq!(handle.join().unwrap().as_deref(), Some("01965864-f8ab-7eb8-912a-a2c999ab110e"));
+/// ```
+#[must_use]
+#[derive(Debug)]
+pub struct RequestIdGuard {
+    previous: Option<String>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl RequestIdGuard {
+    /// Sets the given request id on the current thread, until the guard is dropped.
+    pub fn new(request_id: impl Into<String>) -> Self {
+        let previous = GUARDED_REQUEST_ID.replace(Some(request_id.into()));
+
+        Self {
+            previous,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for RequestIdGuard {
+    fn drop(&mut self) {
+        let request_id = GUARDED_REQUEST_ID.replace(self.previous.take());
+
+        if thread::panicking()
+            && let Some(request_id) = request_id
+        {
+            let request_id = with_default_config(|config| {
+                config.log_redaction().redact(&request_id).into_owned()
+            });
+
+            error!("[{}] Panicked while handling the request", request_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::{RequestIdGuard, current_request_id};
+
+    /// Records the request id seen when it's dropped, i.e. while unwinding, before the guard declared earlier.
+    struct PanicProbe<'a>(&'a mut Option<String>);
+
+    impl Drop for PanicProbe<'_> {
+        fn drop(&mut self) {
+            assert!(std::thread::panicking());
+
+            *self.0 = current_request_id();
+        }
+    }
+
+    #[test]
+    fn test_task_local_guard_during_panic() {
+        let mut seen = None;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = RequestIdGuard::new("01965864-f8ab-7eb8-912a-a2c999ab110e");
+            let _probe = PanicProbe(&mut seen);
+
+            panic!("boom");
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(
+            seen.as_deref(),
+            Some("01965864-f8ab-7eb8-912a-a2c999ab110e")
+        );
+        assert_eq!(current_request_id(), None);
+    }
+
+    #[test]
+    fn test_task_local_guard_nested() {
+        let outer = RequestIdGuard::new("0196583c-4d2a-7087-9beb-6214d18ec924");
+
+        {
+            let _inner = RequestIdGuard::new("01965864-f8ab-7eb8-912a-a2c999ab110e");
+
+            assert_eq!(
+                current_request_id().as_deref(),
+                Some("01965864-f8ab-7eb8-912a-a2c999ab110e")
+            );
+        }
+
+        assert_eq!(
+            current_request_id().as_deref(),
+            Some("0196583c-4d2a-7087-9beb-6214d18ec924")
+        );
+
+        drop(outer);
+
+        assert_eq!(current_request_id(), None);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{ops::Range, sync::OnceLock};
+
+/// A template wrapping the generated request ids, e.g. `req_{uuid}` or `{unix_ms}-{uuid}`, see `RequestIdConfig::with_generation_template`.
+///
+/// The placeholders are expanded at generation time :
+/// - `{uuid}` : the generated UUID v7, formatted per the configured canonical form and letter case ;
+/// - `{unix_ms}` : the timestamp of the UUID v7, in milliseconds since the Unix epoch ;
+/// - `{hostname}` : the hostname of the machine, from the `HOSTNAME` environment variable or `/etc/hostname`.
+///
+/// Any other text, including unknown placeholders, is kept as is.
+///
+/// # Examples
+///
+/// ```rust
+/// use request_id_middleware::IdTemplate;
+///
+/// let template = IdTemplate::new("req_{uuid}");
+///
+/// assert_eq!(
+///     template.expand("01965864-f8ab-7eb8-912a-a2c999ab110e", 1_745_000_000_000),
+///     "req_01965864-f8ab-7eb8-912a-a2c999ab110e"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Uuid,
+    UnixMs,
+    Hostname,
+}
+
+impl IdTemplate {
+    /// Parses the template.
+    pub fn new(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            literal.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let (segment, len) = if rest.starts_with("{uuid}") {
+                (Segment::Uuid, "{uuid}".len())
+            } else if rest.starts_with("{unix_ms}") {
+                (Segment::UnixMs, "{unix_ms}".len())
+            } else if rest.starts_with("{hostname}") {
+                (Segment::Hostname, "{hostname}".len())
+            } else {
+                literal.push('{');
+                rest = &rest[1..];
+                continue;
+            };
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+
+            segments.push(segment);
+            rest = &rest[len..];
+        }
+
+        literal.push_str(rest);
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Self { segments }
+    }
+
+    /// Expands the placeholders with the given request id and timestamp.
+    pub fn expand(&self, request_id: &str, unix_ms: u64) -> String {
+        let mut expanded = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => expanded.push_str(literal),
+                Segment::Uuid => expanded.push_str(request_id),
+                Segment::UnixMs => expanded.push_str(&unix_ms.to_string()),
+                Segment::Hostname => expanded.push_str(hostname()),
+            }
+        }
+
+        expanded
+    }
+
+    /// Matches the value against the template, and returns the range of its `{uuid}` placeholder, if any.
+    ///
+    /// The literal text must match exactly, `{unix_ms}` matches digits, `{hostname}` matches up to the first occurrence
+    /// of the next literal text, and `{uuid}` up to its last occurrence, since UUIDs contain hyphens.
+    pub(crate) fn find_uuid(&self, value: &str) -> Option<Range<usize>> {
+        let mut uuid = None;
+        let mut offset = 0;
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            let rest = &value[offset..];
+
+            let len = match segment {
+                Segment::Literal(literal) => rest
+                    .starts_with(literal.as_str())
+                    .then_some(literal.len())?,
+                Segment::UnixMs => rest.bytes().take_while(u8::is_ascii_digit).count(),
+                Segment::Uuid | Segment::Hostname => match self.segments.get(index + 1) {
+                    Some(Segment::Literal(next)) if *segment == Segment::Uuid => {
+                        rest.rfind(next.as_str())?
+                    }
+                    Some(Segment::Literal(next)) => rest.find(next.as_str())?,
+                    _ => rest.len(),
+                },
+            };
+
+            if *segment == Segment::Uuid {
+                uuid = Some(offset..offset + len);
+            }
+
+            offset += len;
+        }
+
+        (offset == value.len()).then_some(uuid).flatten()
+    }
+}
+
+/// Returns the hostname of the machine, read once, or `localhost` when it's unknown.
+fn hostname() -> &'static str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+
+    HOSTNAME.get_or_init(|| {
+        std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|hostname| hostname.trim().to_string())
+            .filter(|hostname| !hostname.is_empty())
+            .unwrap_or_else(|| "localhost".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IdTemplate;
+
+    #[test]
+    fn test_template_expand() {
+        let template = IdTemplate::new("{unix_ms}-{uuid}.{foo}");
+
+        assert_eq!(
+            template.expand("01965864-f8ab-7eb8-912a-a2c999ab110e", 1_745_000_000_000),
+            "1745000000000-01965864-f8ab-7eb8-912a-a2c999ab110e.{foo}"
+        );
+    }
+
+    #[test]
+    fn test_template_find_uuid() {
+        let request_id = "01965864-f8ab-7eb8-912a-a2c999ab110e";
+
+        assert_eq!(
+            IdTemplate::new("req_{uuid}").find_uuid(&format!("req_{}", request_id)),
+            Some(4..40)
+        );
+        assert_eq!(
+            IdTemplate::new("{unix_ms}-{uuid}").find_uuid(&format!("1745000000000-{}", request_id)),
+            Some(14..50)
+        );
+        assert_eq!(
+            IdTemplate::new("{uuid}-{unix_ms}").find_uuid(&format!("{}-1745000000000", request_id)),
+            Some(0..36)
+        );
+        assert_eq!(IdTemplate::new("req_{uuid}").find_uuid(request_id), None);
+        assert_eq!(IdTemplate::new("req_").find_uuid("req_"), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::SystemTime;
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{ExtractRequestId, RequestIdRejection};
+
+/// This is a custom extractor for Axum that extracts the request id, exactly like `ExtractRequestId`,
+/// along with the time the server received the request, i.e. when the extractor ran.
+///
+/// Compared with the timestamp of an UUID v7 sent by the client, e.g. via `RequestId::system_time`,
+/// it lets a handler attribute the latency between the client and the server.
+///
+/// # Examples
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use request_id_middleware::{ExtractRequestIdTimed, RequestId};
+/// use uuid::Uuid;
+///
+/// async fn handler(ExtractRequestIdTimed { id, received_at }: ExtractRequestIdTimed) {
+///     let sent_at = Uuid::try_parse(&id).ok().and_then(|uuid| RequestId::from(uuid).system_time());
+///
+///     if let Some(latency) = sent_at.and_then(|sent_at| received_at.duration_since(sent_at).ok()) {
+///         println!("Request Id: {:?}, Latency: {:?}", id, latency);
+///     }
+/// }
+///
+/// let app = Router::<()>::new().route("/foo", get(handler));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractRequestIdTimed {
+    /// The request id.
+    pub id: String,
+    /// The time the request was received.
+    pub received_at: SystemTime,
+}
+
+impl<S> FromRequestParts<S> for ExtractRequestIdTimed
+where
+    S: Send + Sync,
+{
+    type Rejection = RequestIdRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let received_at = SystemTime::now();
+        let ExtractRequestId(id) = ExtractRequestId::from_request_parts(parts, state).await?;
+
+        Ok(ExtractRequestIdTimed { id, received_at })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::ExtractRequestIdTimed;
+    use axum::{body::Body, extract::FromRequestParts, http::Request};
+
+    #[tokio::test]
+    async fn test_timed_received_at() {
+        let before = SystemTime::now();
+
+        let mut parts = Request::builder()
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+
+        let ExtractRequestIdTimed { id, received_at } =
+            ExtractRequestIdTimed::from_request_parts(&mut parts.0, &())
+                .await
+                .unwrap();
+
+        assert_eq!(id, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+        assert!(received_at >= before);
+        assert!(received_at.elapsed().unwrap() < Duration::from_secs(5));
+    }
+}
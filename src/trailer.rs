@@ -0,0 +1,69 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderName, HeaderValue},
+};
+use http_body::{Frame, SizeHint};
+
+/// A response body emitting the request id as a trailer once the inner body is done, merged into its own trailers if any.
+pub(crate) struct TrailerBody {
+    inner: Body,
+    trailer: Option<(HeaderName, HeaderValue)>,
+}
+
+impl TrailerBody {
+    pub(crate) fn new(inner: Body, name: HeaderName, value: HeaderValue) -> Self {
+        Self {
+            inner,
+            trailer: Some((name, value)),
+        }
+    }
+}
+
+impl http_body::Body for TrailerBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+
+        match Pin::new(&mut this.inner).poll_frame(cx) {
+            Poll::Ready(Some(Ok(frame))) => {
+                let frame = match frame.into_trailers() {
+                    Ok(mut trailers) => {
+                        if let Some((name, value)) = this.trailer.take() {
+                            trailers.insert(name, value);
+                        }
+
+                        Frame::trailers(trailers)
+                    }
+                    Err(frame) => frame,
+                };
+
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Poll::Ready(None) => Poll::Ready(this.trailer.take().map(|(name, value)| {
+                let mut trailers = HeaderMap::new();
+                trailers.insert(name, value);
+
+                Ok(Frame::trailers(trailers))
+            })),
+            poll => poll,
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.trailer.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,34 @@
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{Request, StatusCode},
+};
+use request_id_middleware::{ExtractRequestId, RequestIdConfig, set_default_config};
+
+#[tokio::test]
+async fn test_default_config_set_once() {
+    assert!(
+        set_default_config(RequestIdConfig::default().with_reject_zero_timestamp(true)).is_ok()
+    );
+    assert!(set_default_config(RequestIdConfig::default()).is_err());
+
+    let request = Request::builder()
+        .header("X-Request-Id", "00000000-0000-7000-8000-000000000001")
+        .body(Body::empty())
+        .unwrap();
+
+    let mut parts = request.into_parts();
+
+    let request_id = ExtractRequestId::from_request_parts(&mut parts.0, &()).await;
+
+    match request_id {
+        Ok(_) => panic!("Expected an error"),
+        Err(err) => assert_eq!(
+            err,
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid X-Request-Id : Zero timestamp".to_string()
+            )
+        ),
+    }
+}
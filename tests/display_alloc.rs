@@ -0,0 +1,42 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use request_id_middleware::RequestId;
+use uuid::Uuid;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the heap allocations of the test binary, forwarding them to the system allocator.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn test_display_no_allocation() {
+    let request_id =
+        RequestId::from(Uuid::try_parse("01965864-f8ab-7eb8-912a-a2c999ab110e").unwrap());
+    let mut buffer = String::with_capacity(64);
+
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+
+    write!(buffer, "{}", request_id).unwrap();
+
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+    assert_eq!(buffer, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+}
@@ -0,0 +1,36 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use request_id_middleware::{ExtractRequestId, RequestIdConfig, set_default_config};
+use tower::ServiceExt;
+
+async fn handler(request_id: ExtractRequestId) -> (ExtractRequestId, StatusCode) {
+    (request_id, StatusCode::OK)
+}
+
+#[tokio::test]
+async fn test_id_prefix_into_response_parts() {
+    set_default_config(RequestIdConfig::default().with_id_prefix("svc-")).unwrap();
+
+    // Without the layer, the extractor strips the prefix, and puts it back on the response header.
+    let app = Router::new().route("/", get(handler));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header("X-Request-Id", "svc-01965864-f8ab-7eb8-912a-a2c999ab110e")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.headers()["x-request-id"],
+        "svc-01965864-f8ab-7eb8-912a-a2c999ab110e"
+    );
+}
@@ -0,0 +1,78 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use http_body_util::BodyExt;
+use request_id_middleware::{ExtractRequestId, RequestIdConfig, layered};
+use tower::ServiceExt;
+
+async fn handler(ExtractRequestId(request_id): ExtractRequestId) -> String {
+    request_id
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/foo", get(handler))
+        .layer(layered(RequestIdConfig::default()))
+}
+
+#[tokio::test]
+async fn test_layered_with_header_ok() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/foo")
+                .header("X-Request-Id", " 01965864-F8AB-7eb8-912a-a2c999ab110e ")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("x-request-id").unwrap(),
+        "01965864-f8ab-7eb8-912a-a2c999ab110e"
+    );
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    assert_eq!(body, "01965864-f8ab-7eb8-912a-a2c999ab110e");
+}
+
+#[tokio::test]
+async fn test_layered_with_header_ko() {
+    let response = app()
+        .oneshot(
+            Request::builder()
+                .uri("/foo")
+                .header("X-Request-Id", "6edaba95-4f5b-4547-be3f-85210d3ff8bf")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    assert_eq!(body, "Invalid X-Request-Id : Not an UUID v7");
+}
+
+#[tokio::test]
+async fn test_layered_without_header() {
+    let response = app()
+        .oneshot(Request::builder().uri("/foo").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let header = response.headers().get("x-request-id").unwrap().clone();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    assert_eq!(header.as_bytes(), body.as_ref());
+}
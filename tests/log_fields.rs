@@ -0,0 +1,63 @@
+use std::sync::Mutex;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use log::{LevelFilter, Log, Metadata, Record};
+use request_id_middleware::{LogField, RequestIdConfig, RequestIdLayer};
+use tower::ServiceExt;
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LINES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[tokio::test]
+async fn test_log_fields() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(LevelFilter::Error);
+
+    let app = Router::new()
+        .route("/log-fields", post(|| async {}))
+        .layer(RequestIdLayer::new(
+            RequestIdConfig::default().with_log_fields(vec![
+                LogField::Method,
+                LogField::Path,
+                LogField::ClientIp,
+                LogField::UserAgent,
+            ]),
+        ));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/log-fields?foo=bar")
+                .header("x-request-id", "foo")
+                .header("x-forwarded-for", "203.0.113.43")
+                .header("user-agent", "curl/8.7.1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(LINES.lock().unwrap().contains(
+        &"[x-request-id] Rejected request id due to : Not a valid UUID (method=POST path=/log-fields client_ip=203.0.113.43 user_agent=\"curl/8.7.1\")".to_string()
+    ));
+}
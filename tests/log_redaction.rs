@@ -0,0 +1,114 @@
+use std::sync::Mutex;
+
+use axum::{Router, body::Body, http::Request, routing::get};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use request_id_middleware::{
+    InFlightLayer, Redaction, RequestIdConfig, RequestIdLayer, set_default_config,
+};
+use tower::ServiceExt;
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        LINES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+async fn log_lines(redaction: Redaction) -> Vec<String> {
+    LINES.lock().unwrap().clear();
+
+    let app = Router::new()
+        .route("/", get(|| async {}))
+        .layer(RequestIdLayer::new(
+            RequestIdConfig::default().with_log_redaction(redaction),
+        ));
+
+    app.oneshot(
+        Request::builder()
+            .uri("/")
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    LINES.lock().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_log_redaction() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+
+    // With the `tracing` feature, the request span is logged too, and must be redacted as well.
+    let lines = log_lines(Redaction::None).await;
+
+    assert!(lines.contains(
+        &"[x-request-id] Resolved request id : 01965864-f8ab-7eb8-912a-a2c999ab110e".to_string()
+    ));
+
+    let lines = log_lines(Redaction::Shortened).await;
+
+    assert!(lines.contains(&"[x-request-id] Resolved request id : ...99ab110e".to_string()));
+    assert!(
+        lines.iter().all(|line| !line.contains("01965864")),
+        "{:?}",
+        lines
+    );
+
+    let lines = log_lines(Redaction::Hashed).await;
+    let hashed = Redaction::Hashed.redact("01965864-f8ab-7eb8-912a-a2c999ab110e");
+
+    assert!(lines.contains(&format!("[x-request-id] Resolved request id : {}", hashed)));
+    assert!(
+        lines.iter().all(|line| !line.contains("01965864")),
+        "{:?}",
+        lines
+    );
+
+    // The layers without a configuration of their own log per the process-global one.
+    set_default_config(RequestIdConfig::default().with_log_redaction(Redaction::Shortened))
+        .unwrap();
+
+    LINES.lock().unwrap().clear();
+
+    let app = Router::new()
+        .route("/", get(|| async {}))
+        .layer(InFlightLayer::new().with_limit(0))
+        .layer(RequestIdLayer::new(
+            RequestIdConfig::default().with_log_redaction(Redaction::Shortened),
+        ));
+
+    app.oneshot(
+        Request::builder()
+            .uri("/")
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let lines = LINES.lock().unwrap().clone();
+
+    assert!(
+        lines.contains(
+            &"[X-Request-Id] Rejected request already in flight : ...99ab110e".to_string()
+        )
+    );
+    assert!(
+        lines.iter().all(|line| !line.contains("01965864")),
+        "{:?}",
+        lines
+    );
+}
@@ -0,0 +1,50 @@
+//! The panic hook is process-global, so this test lives in its own binary.
+
+#![cfg(feature = "task-local")]
+
+use std::{
+    panic,
+    sync::{Arc, Mutex},
+};
+
+use axum::{Router, body::Body, http::Request, routing::get};
+use request_id_middleware::{RequestIdConfig, RequestIdLayer, current_request_id};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn test_task_local_panic_hook() {
+    let panicked = Arc::new(Mutex::new(Vec::new()));
+
+    panic::set_hook({
+        let panicked = panicked.clone();
+
+        Box::new(move |_| panicked.lock().unwrap().push(current_request_id()))
+    });
+
+    let app = Router::new()
+        .route(
+            "/",
+            get(|| async {
+                let _ = panic::catch_unwind(|| panic!("Simulated crash"));
+            }),
+        )
+        .layer(RequestIdLayer::new(RequestIdConfig::default()));
+
+    app.oneshot(
+        Request::builder()
+            .uri("/")
+            .header("X-Request-Id", "01965864-f8ab-7eb8-912a-a2c999ab110e")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let _ = panic::take_hook();
+
+    assert_eq!(
+        *panicked.lock().unwrap(),
+        vec![Some("01965864-f8ab-7eb8-912a-a2c999ab110e".to_string())]
+    );
+    assert_eq!(current_request_id(), None);
+}